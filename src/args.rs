@@ -0,0 +1,490 @@
+use std::env;
+use std::fs;
+use std::io::stdout;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crossterm::tty::IsTty;
+
+use crate::filter::ProcessFilter;
+use crate::push::{PushFormat, PushTarget};
+use crate::ui::{self, SortColumn};
+
+/// The shortest refresh interval, faster refreshes cost more than they show
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The options of the command line, the short name, the long name, the name of the
+/// value if the option takes one and the description shown by `--help`
+const OPTIONS: [(Option<char>, &str, Option<&str>, &str); 19] = [
+    (Some('d'), "delay", Some("SECONDS"), "Refresh interval in seconds, at least 0.1 (default 1)"),
+    (Some('t'), "tab", Some("TAB"), "Initial tab: overview, processes, details, threads, sensors, cpu or graphs"),
+    (Some('s'), "sort", Some("COLUMN"), "Sort processes by pid, user, state, nice, cpu, mem, virt, threads, start or command"),
+    (Some('f'), "filter", Some("EXPR"), "Only show the processes matching the filter expression"),
+    (Some('p'), "pid", Some("PIDS"), "Only show the processes with the comma separated ids"),
+    (Some('u'), "user", Some("USER"), "Only show the processes of the user"),
+    (None, "color", Some("WHEN"), "Use colours: auto, always or never (default auto)"),
+    (Some('c'), "config", Some("FILE"), "Read the options from the file (default ~/.config/rctop/config)"),
+    (Some('b'), "batch", None, "Print snapshots as text instead of the interactive UI"),
+    (Some('n'), "iterations", Some("COUNT"), "Exit after COUNT refreshes"),
+    (Some('o'), "output", Some("FORMAT"), "Output format of the batch mode: text, json or ndjson (default text)"),
+    (Some('r'), "record", Some("FILE"), "Append the CPU, memory, disk and network usage to a CSV file on every refresh"),
+    (Some('l'), "listen", Some("ADDR"), "Serve the metrics for Prometheus on http://ADDR/metrics instead of the UI, a port listens on localhost"),
+    (None, "push", Some("URL"), "Send the metrics to tcp://HOST:PORT or udp://HOST:PORT on every refresh"),
+    (None, "push-format", Some("FORMAT"), "Format of the pushed metrics: influx or graphite (default influx)"),
+    (None, "push-tags", Some("TAGS"), "Comma separated KEY=VALUE tags added to the pushed metrics, host is the hostname by default"),
+    (None, "json", None, "Print a single snapshot as JSON and exit, same as --batch --output json"),
+    (Some('h'), "help", None, "Print this help and exit"),
+    (Some('V'), "version", None, "Print the version and exit"),
+];
+
+/// When the UI is printed in colour
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorMode {
+    /// Colours on terminals, unless `NO_COLOR` is set or the terminal is dumb
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Checks if the output should be coloured
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                env::var_os("NO_COLOR").is_none()
+                    && env::var("TERM").map_or(true, |term| term != "dumb")
+                    && stdout().is_tty()
+            }
+        }
+    }
+}
+
+/// The formats of the batch mode output
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutputFormat {
+    Text,
+    /// A single pretty printed JSON object
+    Json,
+    /// One JSON object per line for every refresh
+    Ndjson,
+}
+
+/// Contains the runtime options given on the command line and in the config file
+/// ### Fields
+/// * `interval` - The time between the refreshes
+/// * `tab` - The tab shown at start
+/// * `sort` - The column the processes are sorted by, the default sort if none
+/// * `filter` - The process filter expression made of the filter, PID and user options
+/// * `color` - When the UI is printed in colour
+/// * `batch` - Whether snapshots are printed instead of the interactive UI
+/// * `iterations` - The count of refreshes before exiting, unlimited if none
+/// * `output` - The format of the batch mode output
+/// * `record` - The CSV file the metrics are recorded to, if any
+/// * `listen` - The address the Prometheus metrics are served on, if any
+/// * `push` - Where the metrics are pushed on every refresh, if anywhere
+pub struct Options {
+    pub interval: Duration,
+    pub tab: usize,
+    pub sort: Option<SortColumn>,
+    pub filter: Option<String>,
+    pub color: ColorMode,
+    pub batch: bool,
+    pub iterations: Option<u64>,
+    pub output: OutputFormat,
+    pub record: Option<PathBuf>,
+    pub listen: Option<String>,
+    pub push: Option<PushTarget>,
+}
+
+/// What the program was asked to do on the command line
+pub enum Action {
+    Run(Options),
+    Help,
+    Version,
+}
+
+/// The filter parts given as separate options, combined into one expression at the end
+#[derive(Default)]
+struct FilterParts {
+    filter: Option<String>,
+    pids: Option<String>,
+    user: Option<String>,
+}
+
+/// The push options, combined into the push target at the end
+#[derive(Default)]
+struct PushParts {
+    url: Option<String>,
+    format: Option<PushFormat>,
+    tags: Option<String>,
+}
+
+/// Parses the command line arguments and the config file, the arguments override the
+/// config file, returns a description of the problem for invalid ones
+/// ### Parameters
+/// * `arguments` - The arguments without the program name
+/// * `default_config` - The config file read when none is given, if it exists
+pub fn parse(arguments: &[String], default_config: Option<PathBuf>) -> Result<Action, String> {
+    let arguments = split_arguments(arguments)?;
+    for (name, _) in &arguments {
+        match name.as_str() {
+            "help" => return Ok(Action::Help),
+            "version" => return Ok(Action::Version),
+            _ => {}
+        }
+    }
+    // The config file is read first so that the arguments can override it
+    let config = arguments.iter().rev().find(|(name, _)| name == "config").and_then(|(_, value)| value.clone());
+    let config = match config {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            let content = fs::read_to_string(&path).map_err(|e| format!("Can't read the config file {}: {}", path.display(), e))?;
+            Some((content, path))
+        }
+        None => default_config.and_then(|path| Some((fs::read_to_string(&path).ok()?, path))),
+    };
+    let entries = match config {
+        Some((content, path)) => parse_config(&content).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Vec::new(),
+    };
+    let mut options = Options {
+        interval: Duration::from_secs(1),
+        tab: ui::TAB_OVERVIEW,
+        sort: None,
+        filter: None,
+        color: ColorMode::Auto,
+        batch: false,
+        iterations: None,
+        output: OutputFormat::Text,
+        record: None,
+        listen: None,
+        push: None,
+    };
+    let mut parts = FilterParts::default();
+    let mut push = PushParts::default();
+    for (name, value) in entries.iter().chain(arguments.iter()) {
+        apply(&mut options, &mut parts, &mut push, name, value.as_deref())?;
+    }
+    let expression: Vec<String> = vec![parts.pids.map(|pids| format!("pid:{}", pids)), parts.user.map(|user| format!("user:{}", user)), parts.filter]
+        .into_iter()
+        .flatten()
+        .collect();
+    if !expression.is_empty() {
+        let expression = expression.join(" ");
+        ProcessFilter::parse(&expression)?;
+        options.filter = Some(expression);
+    }
+    match push.url {
+        Some(url) => options.push = Some(PushTarget::parse(&url, push.format.unwrap_or(PushFormat::Influx), push.tags.as_deref())?),
+        None if push.format.is_some() || push.tags.is_some() => return Err(String::from("The push format and tags need --push")),
+        None => {}
+    }
+    // Only the batch mode has an output format, asking for one asks for the batch mode
+    if options.output != OutputFormat::Text {
+        options.batch = true;
+    }
+    if options.batch && options.listen.is_some() {
+        return Err(String::from("The exporter can't be combined with the batch mode"));
+    }
+    if options.output == OutputFormat::Json && options.iterations.is_some_and(|iterations| iterations > 1) {
+        return Err(String::from("The json output is a single snapshot, use --output ndjson for more"));
+    }
+    Ok(Action::Run(options))
+}
+
+/// Returns the help text listing every option
+pub fn help() -> String {
+    let mut text = format!(
+        "rctop {}\nA terminal system monitor\n\nUsage: rctop [OPTIONS]\n\nOptions:\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    for (short, long, value, description) in OPTIONS.iter() {
+        let short = short.map_or(String::from("    "), |short| format!("-{}, ", short));
+        let long = match value {
+            Some(value) => format!("--{} <{}>", long, value),
+            None => format!("--{}", long),
+        };
+        text += &format!("  {}{:<22} {}\n", short, long, description);
+    }
+    text += "\nThe config file has one option per line without the dashes, such as `delay = 2`,\nlines starting with # are comments.\n";
+    text
+}
+
+/// Returns the version text
+pub fn version() -> String {
+    format!("rctop {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Splits the arguments into the long option names and their values, checks that the
+/// options exist and that the ones taking a value have one
+/// ### Parameters
+/// * `arguments` - The arguments without the program name
+fn split_arguments(arguments: &[String]) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut options: Vec<(String, Option<String>)> = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        // Both `--delay 2` and `--delay=2`, and both `-d 2` and `-d2` work
+        let (option, attached) = if let Some(long) = argument.strip_prefix("--") {
+            let (name, value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            (OPTIONS.iter().find(|(_, long, _, _)| *long == name), value)
+        } else if argument.len() > 1 && argument.starts_with('-') {
+            let mut chars = argument[1..].chars();
+            let short = chars.next();
+            let rest: String = chars.collect();
+            (OPTIONS.iter().find(|(option, _, _, _)| *option == short), Some(rest).filter(|rest| !rest.is_empty()))
+        } else {
+            return Err(format!("Unexpected argument '{}', see --help", argument));
+        };
+        let (_, long, value_name, _) = option.ok_or_else(|| format!("Unknown option '{}', see --help", argument))?;
+        let value = match (value_name, attached) {
+            (Some(_), Some(value)) => Some(value),
+            (Some(value_name), None) => Some(iter.next().cloned().ok_or_else(|| format!("Option '{}' needs a {}", argument, value_name))?),
+            (None, Some(_)) => return Err(format!("Option '{}' doesn't take a value", argument)),
+            (None, None) => None,
+        };
+        options.push((long.to_string(), value));
+    }
+    Ok(options)
+}
+
+/// Parses the lines of a config file into the long option names and their values
+/// ### Parameters
+/// * `content` - The content of the config file
+fn parse_config(content: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut entries: Vec<(String, Option<String>)> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"').to_string())),
+            None => (line, None),
+        };
+        let option = OPTIONS.iter().find(|(_, long, _, _)| *long == name);
+        match option {
+            Some((_, "config", _, _)) | Some((_, "help", _, _)) | Some((_, "version", _, _)) | None => {
+                return Err(format!("line {}: unknown option '{}'", number + 1, name));
+            }
+            Some((_, long, Some(_), _)) => {
+                let value = value.ok_or_else(|| format!("line {}: option '{}' needs a value", number + 1, name))?;
+                entries.push((long.to_string(), Some(value)));
+            }
+            // Flags can be turned off in the config file with `batch = false`
+            Some((_, long, None, _)) => match value.as_deref() {
+                None | Some("true") | Some("yes") | Some("1") => entries.push((long.to_string(), None)),
+                Some("false") | Some("no") | Some("0") => {}
+                Some(value) => return Err(format!("line {}: invalid value '{}' for '{}'", number + 1, value, name)),
+            },
+        }
+    }
+    Ok(entries)
+}
+
+/// Applies a single option
+/// ### Parameters
+/// * `options` - The options to change
+/// * `parts` - The filter parts to change
+/// * `push` - The push parts to change
+/// * `name` - The long name of the option
+/// * `value` - The value of the option, none for flags
+fn apply(options: &mut Options, parts: &mut FilterParts, push: &mut PushParts, name: &str, value: Option<&str>) -> Result<(), String> {
+    let value = value.unwrap_or("");
+    match name {
+        "delay" => {
+            let seconds: f64 = value.parse().map_err(|_| format!("Invalid refresh interval '{}'", value))?;
+            if !seconds.is_finite() || seconds < MIN_INTERVAL.as_secs_f64() {
+                return Err(format!("The refresh interval must be at least {} seconds", MIN_INTERVAL.as_secs_f64()));
+            }
+            options.interval = Duration::try_from_secs_f64(seconds).map_err(|_| format!("The refresh interval '{}' is too long", value))?;
+        }
+        "tab" => {
+            options.tab = ui::TABS
+                .iter()
+                .position(|tab| tab.eq_ignore_ascii_case(value))
+                .or_else(|| value.parse::<usize>().ok().filter(|tab| *tab < ui::TABS.len()))
+                .ok_or_else(|| format!("Unknown tab '{}'", value))?;
+        }
+        "sort" => options.sort = Some(SortColumn::from_name(value).ok_or_else(|| format!("Unknown sort column '{}'", value))?),
+        "filter" => parts.filter = Some(value.to_string()),
+        "pid" => parts.pids = Some(value.to_string()),
+        "user" => parts.user = Some(value.to_string()),
+        "color" => {
+            options.color = match value {
+                "auto" => ColorMode::Auto,
+                "always" => ColorMode::Always,
+                "never" => ColorMode::Never,
+                _ => return Err(format!("Invalid colour mode '{}', use auto, always or never", value)),
+            }
+        }
+        "batch" => options.batch = true,
+        "iterations" => {
+            let iterations: u64 = value.parse().map_err(|_| format!("Invalid iteration count '{}'", value))?;
+            if iterations == 0 {
+                return Err(String::from("The iteration count must be at least 1"));
+            }
+            options.iterations = Some(iterations);
+        }
+        "output" => {
+            options.output = match value {
+                "text" => OutputFormat::Text,
+                "json" => OutputFormat::Json,
+                "ndjson" => OutputFormat::Ndjson,
+                _ => return Err(format!("Unknown output format '{}'", value)),
+            }
+        }
+        "json" => options.output = OutputFormat::Json,
+        "record" => options.record = Some(PathBuf::from(value)),
+        "push" => push.url = Some(value.to_string()),
+        "push-format" => push.format = Some(PushFormat::from_name(value).ok_or_else(|| format!("Unknown push format '{}', use influx or graphite", value))?),
+        "push-tags" => push.tags = Some(value.to_string()),
+        "listen" => {
+            // A port alone only listens on localhost, other hosts have to be asked for
+            options.listen = Some(match value.parse::<u16>() {
+                Ok(port) => format!("127.0.0.1:{}", port),
+                Err(_) if value.contains(':') => value.to_string(),
+                Err(_) => return Err(format!("Invalid listen address '{}', use a port or HOST:PORT", value)),
+            });
+        }
+        // Read before the other options
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Returns the path of the default config file, `$XDG_CONFIG_HOME/rctop/config` or
+/// `~/.config/rctop/config`
+pub fn default_config() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_CONFIG_HOME").filter(|directory| !directory.is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(directory.join("rctop").join("config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn run(arguments: &[&str]) -> Result<Options, String> {
+        let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+        match parse(&arguments, None)? {
+            Action::Run(options) => Ok(options),
+            _ => Err(String::from("not a run")),
+        }
+    }
+
+    #[test]
+    fn defaults_without_arguments() {
+        let options = run(&[]).unwrap();
+        assert_eq!(options.interval, Duration::from_secs(1));
+        assert_eq!(options.tab, ui::TAB_OVERVIEW);
+        assert!(options.sort.is_none() && options.filter.is_none());
+        assert_eq!(options.color, ColorMode::Auto);
+        assert!(!options.batch);
+        assert_eq!(options.iterations, None);
+        assert_eq!(options.output, OutputFormat::Text);
+        assert_eq!(options.record, None);
+    }
+
+    #[test]
+    fn parses_long_and_short_options() {
+        let options = run(&["-d", "0.5", "--tab=processes", "-smem", "--color", "never", "-b", "-n", "3"]).unwrap();
+        assert_eq!(options.interval, Duration::from_millis(500));
+        assert_eq!(options.tab, ui::TAB_PROCESSES);
+        assert!(options.sort == Some(SortColumn::Memory));
+        assert_eq!(options.color, ColorMode::Never);
+        assert!(options.batch);
+        assert_eq!(options.iterations, Some(3));
+        let options = run(&["-r", "/tmp/rctop.csv"]).unwrap();
+        assert_eq!(options.record, Some(PathBuf::from("/tmp/rctop.csv")));
+    }
+
+    #[test]
+    fn parses_the_listen_address() {
+        assert_eq!(run(&["-l", "9101"]).unwrap().listen.as_deref(), Some("127.0.0.1:9101"));
+        assert_eq!(run(&["--listen", "0.0.0.0:9101"]).unwrap().listen.as_deref(), Some("0.0.0.0:9101"));
+        assert_eq!(run(&["--listen", "[::]:9101"]).unwrap().listen.as_deref(), Some("[::]:9101"));
+        assert!(run(&["--listen", "localhost"]).is_err());
+        assert!(run(&["--listen", "9101", "--batch"]).is_err());
+        assert!(run(&["--listen", "9101", "--json"]).is_err());
+    }
+
+    #[test]
+    fn parses_the_push_options() {
+        assert!(run(&[]).unwrap().push.is_none());
+        let options = run(&["--push", "udp://127.0.0.1:8089", "--push-tags", "host=db,role=primary"]).unwrap();
+        assert!(options.push == PushTarget::parse("udp://127.0.0.1:8089", PushFormat::Influx, Some("host=db,role=primary")).ok());
+        let options = run(&["--push=tcp://graphite:2003", "--push-format", "graphite"]).unwrap();
+        assert!(options.push == PushTarget::parse("tcp://graphite:2003", PushFormat::Graphite, None).ok());
+        assert!(run(&["--push", "tcp://graphite:2003", "--push-format", "statsd"]).is_err());
+        assert!(run(&["--push-format", "graphite"]).is_err());
+    }
+
+    #[test]
+    fn combines_the_filter_options() {
+        let options = run(&["-p", "1,42", "-u", "root", "--filter", "cpu>5"]).unwrap();
+        assert_eq!(options.filter.as_deref(), Some("pid:1,42 user:root cpu>5"));
+        assert!(run(&["-p", "1,x"]).is_err());
+        assert!(run(&["--filter", "re:("]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(run(&["--delay", "0.05"]).is_err());
+        assert!(run(&["--delay", "1e20"]).is_err());
+        assert!(run(&["--delay", "inf"]).is_err());
+        assert!(run(&["--delay"]).is_err());
+        assert!(run(&["--tab", "nope"]).is_err());
+        assert!(run(&["--sort", "nope"]).is_err());
+        assert!(run(&["--batch=yes"]).is_err());
+        assert!(run(&["-n", "0"]).is_err());
+        assert!(run(&["--unknown"]).is_err());
+        assert!(run(&["stray"]).is_err());
+    }
+
+    #[test]
+    fn json_output_implies_the_batch_mode() {
+        let options = run(&["--json"]).unwrap();
+        assert!(options.batch);
+        assert_eq!(options.output, OutputFormat::Json);
+        let options = run(&["-o", "ndjson", "-n", "5"]).unwrap();
+        assert!(options.batch);
+        assert_eq!(options.output, OutputFormat::Ndjson);
+        assert!(run(&["--json", "-n", "2"]).is_err());
+        assert!(run(&["-o", "xml"]).is_err());
+    }
+
+    #[test]
+    fn help_and_version_win() {
+        let arguments = vec![String::from("--tab=nope"), String::from("-h")];
+        assert!(matches!(parse(&arguments, None), Ok(Action::Help)));
+        assert!(matches!(parse(&[String::from("-V")], None), Ok(Action::Version)));
+        assert!(help().contains("--iterations <COUNT>"));
+    }
+
+    #[test]
+    fn arguments_override_the_config_file() {
+        let path = env::temp_dir().join(format!("rctop-config-{}", process::id()));
+        fs::write(&path, "# Refresh quickly\ndelay = 0.25\ntab = cpu\nbatch = false\nuser = \"www-data\"\n").unwrap();
+        let config = path.to_string_lossy().into_owned();
+        let options = run(&["--config", &config, "--tab", "graphs"]).unwrap();
+        assert_eq!(options.interval, Duration::from_millis(250));
+        assert_eq!(options.tab, ui::TAB_GRAPHS);
+        assert!(!options.batch);
+        assert_eq!(options.filter.as_deref(), Some("user:www-data"));
+        // The default config is only read when it exists
+        let arguments: Vec<String> = Vec::new();
+        assert!(matches!(parse(&arguments, Some(path.with_extension("missing"))), Ok(Action::Run(_))));
+        fs::write(&path, "colour = never\n").unwrap();
+        assert!(run(&["--config", &config]).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(run(&["--config", &config]).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::error::Error;
+use std::io::{self, ErrorKind, Write};
+use std::slice;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::args::{Options, OutputFormat};
+use crate::datafetcher::{self, CPULoad, ProcessInfo, SystemData};
+use crate::filter::ProcessFilter;
+use crate::json;
+use crate::push::Pusher;
+use crate::record::Recorder;
+use crate::ui::{self, SortColumn};
+use crate::utils;
+
+/// Prints snapshots of the system data to stdout at the refresh interval without touching
+/// the terminal, until the iteration count is reached or stdout is closed, the CPU usage of
+/// the first text snapshot is the average since boot like in `top -b`, the single JSON
+/// snapshot is taken one interval after the start so that the usages and rates are current
+/// ### Parameters
+/// * `options` - The command line options
+pub fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let pusher = options.push.clone().map(Pusher::start);
+    let result = print_snapshots(options, pusher.as_ref());
+    // The last refreshes are still on their way to the endpoint
+    if let Some(pusher) = pusher {
+        pusher.stop();
+    }
+    result
+}
+
+/// Prints the snapshots and records and pushes them, see `run`
+/// ### Parameters
+/// * `options` - The command line options
+/// * `pusher` - The endpoint every snapshot is pushed to, if any
+fn print_snapshots(options: &Options, pusher: Option<&Pusher>) -> Result<(), Box<dyn Error>> {
+    let filter = match &options.filter {
+        Some(expression) => Some(ProcessFilter::parse(expression)?),
+        None => None,
+    };
+    let sort = options.sort.unwrap_or(SortColumn::Cpu);
+    let mut recorder = match &options.record {
+        Some(path) => Some(Recorder::open(path.clone())?),
+        None => None,
+    };
+    let mut push_error: Option<String> = None;
+    let stdout = io::stdout();
+    let (mut sys, mut state) = datafetcher::start_data_fetcher()?;
+    let mut deadline = Instant::now();
+    if options.output == OutputFormat::Json {
+        datafetcher::wait_for_next_fetch(&mut deadline, options.interval);
+        sys = datafetcher::fetch_data(&mut state, None)?;
+    }
+    let mut printed: u64 = 0;
+    loop {
+        let mut out = stdout.lock();
+        let result = match options.output {
+            OutputFormat::Text => write_snapshot(&mut out, &sys, filter.as_ref(), sort),
+            OutputFormat::Json => {
+                let snapshot = json::snapshot(&sys, &processes(&sys, filter.as_ref(), sort));
+                serde_json::to_writer_pretty(&mut out, &snapshot).map_err(io::Error::from).and_then(|_| writeln!(out))
+            }
+            OutputFormat::Ndjson => {
+                let snapshot = json::snapshot(&sys, &processes(&sys, filter.as_ref(), sort));
+                serde_json::to_writer(&mut out, &snapshot).map_err(io::Error::from).and_then(|_| writeln!(out))
+            }
+        }
+        .and_then(|_| out.flush());
+        match result {
+            // Piping into `head` closes stdout early, that is a normal way to stop
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        drop(out);
+        if let Some(recorder) = &mut recorder {
+            recorder.record(&sys)?;
+        }
+        if let Some(pusher) = pusher {
+            pusher.push(&sys);
+            // Stdout may be the snapshots, the push problems go to stderr once per change
+            let error = pusher.error();
+            if error != push_error {
+                match &error {
+                    Some(error) => eprintln!("rctop: push failed: {}", error),
+                    None => eprintln!("rctop: push recovered"),
+                }
+                push_error = error;
+            }
+        }
+        printed += 1;
+        if options.output == OutputFormat::Json || options.iterations.is_some_and(|iterations| printed >= iterations) {
+            return Ok(());
+        }
+        datafetcher::wait_for_next_fetch(&mut deadline, options.interval);
+        sys = datafetcher::fetch_data(&mut state, None)?;
+    }
+}
+
+/// Writes a snapshot of the summary and the process list, followed by an empty line
+/// ### Parameters
+/// * `out` - Where the snapshot is written
+/// * `sys` - The system data to write
+/// * `filter` - The filter the processes must match, if any
+/// * `sort` - The column the processes are sorted by
+fn write_snapshot(out: &mut impl Write, sys: &SystemData, filter: Option<&ProcessFilter>, sort: SortColumn) -> io::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+    let (_year, _month, _day, hour, minute, second) = utils::local_time(now);
+    write!(out, "rctop - {:02}:{:02}:{:02} up {}", hour, minute, second, utils::parse_time(&sys.uptime).trim_end())?;
+    if let Some(load) = sys.cpu.load_average.last() {
+        write!(out, ", load average: {:.2}, {:.2}, {:.2}", load.one, load.five, load.fifteen)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "Tasks: {} total, {} running, {} processes", sys.cpu.tasks_total, sys.cpu.tasks_running, sys.processes.count)?;
+
+    writeln!(out, "{}", cpu_line("%Cpu(s):", &sys.cpu.load))?;
+    for (i, load) in sys.cpu.load.iter().enumerate() {
+        writeln!(out, "{}", cpu_line(&format!("%Cpu{}:", i), slice::from_ref(load)))?;
+    }
+
+    let ram = &sys.ram;
+    writeln!(
+        out,
+        "Mem:  {} total, {} used, {} free, {} available, {} buff/cache",
+        utils::parse_size(&ram.total),
+        utils::parse_size(&ram.used),
+        utils::parse_size(&ram.free),
+        utils::parse_size(&ram.available),
+        utils::parse_size(&(ram.buffers + ram.cached)),
+    )?;
+    writeln!(
+        out,
+        "Swap: {} total, {} used, {} free",
+        utils::parse_size(&ram.swap_total),
+        utils::parse_size(&ram.swap_total.saturating_sub(ram.swap_free)),
+        utils::parse_size(&ram.swap_free),
+    )?;
+    writeln!(out)?;
+
+    writeln!(
+        out,
+        "{:>7} {:<9} S {:>3} {:>6} {:>9} {:>9} {:>4} {:>5} COMMAND",
+        "PID", "USER", "NI", "CPU%", "RSS", "VIRT", "THR", "START"
+    )?;
+    for process in processes(sys, filter, sort) {
+        writeln!(
+            out,
+            "{:>7} {:<9} {} {:>3} {:>6.1} {:>9} {:>9} {:>4} {:>5} {}",
+            process.pid,
+            ui::truncate_end(&process.user, 9),
+            process.state,
+            process.nice,
+            process.cpu,
+            utils::parse_size(&process.rss),
+            utils::parse_size(&process.virt),
+            process.threads,
+            utils::parse_start_time(&process.start_time, &now),
+            process.command,
+        )?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Returns the processes matching the filter in the sort order
+/// ### Parameters
+/// * `sys` - The system data holding the processes
+/// * `filter` - The filter the processes must match, if any
+/// * `sort` - The column the processes are sorted by
+fn processes<'a>(sys: &'a SystemData, filter: Option<&ProcessFilter>, sort: SortColumn) -> Vec<&'a ProcessInfo> {
+    let mut processes: Vec<&ProcessInfo> = match filter {
+        Some(filter) => sys.processes.list.iter().filter(|process| filter.matches(process)).collect(),
+        None => sys.processes.list.iter().collect(),
+    };
+    ui::sort_processes(&mut processes, sort, sort.default_descending());
+    processes
+}
+
+/// Formats the average time the cores spent in each state as percentages like top does
+/// ### Parameters
+/// * `label` - The label of the line
+/// * `loads` - The time fractions of the cores
+fn cpu_line(label: &str, loads: &[CPULoad]) -> String {
+    let cores = loads.len().max(1) as f32;
+    let percentage = |state: fn(&CPULoad) -> f32| loads.iter().map(state).sum::<f32>() / cores * 100_f32;
+    format!(
+        "{:<8} {:5.1} us, {:5.1} sy, {:5.1} ni, {:5.1} id, {:5.1} wa, {:5.1} hi, {:5.1} si, {:5.1} st",
+        label,
+        percentage(|load| load.user),
+        percentage(|load| load.system),
+        percentage(|load| load.nice),
+        percentage(|load| load.idle),
+        percentage(|load| load.iowait),
+        percentage(|load| load.interrupt),
+        percentage(|load| load.softirq),
+        percentage(|load| load.steal),
+    )
+}
@@ -0,0 +1,71 @@
+use crossterm::style::{self, Attribute, Color, SetAttribute};
+use crossterm::Command;
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the UI is printed in colour, set once from the command line
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns the colours on or off
+/// ### Parameters
+/// * `enabled` - Whether the colour commands print colours
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks if the colours are on
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Sets the foreground colour like the crossterm command, prints nothing while the
+/// colours are off
+pub struct SetForegroundColor(pub Color);
+
+impl Command for SetForegroundColor {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if enabled() {
+            style::SetForegroundColor(self.0).write_ansi(f)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        if enabled() {
+            style::SetForegroundColor(self.0).execute_winapi()?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets the background colour like the crossterm command, while the colours are off any
+/// background other than the default reverses the text instead so highlights stay visible
+pub struct SetBackgroundColor(pub Color);
+
+impl Command for SetBackgroundColor {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if enabled() {
+            style::SetBackgroundColor(self.0).write_ansi(f)
+        } else if self.0 == Color::Reset {
+            SetAttribute(Attribute::NoReverse).write_ansi(f)
+        } else {
+            SetAttribute(Attribute::Reverse).write_ansi(f)
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> crossterm::Result<()> {
+        if enabled() {
+            style::SetBackgroundColor(self.0).execute_winapi()
+        } else if self.0 == Color::Reset {
+            style::ResetColor.execute_winapi()
+        } else {
+            // The console API has no reverse attribute, dark text on a light background
+            // stands in for it
+            style::SetBackgroundColor(Color::Grey).execute_winapi()?;
+            style::SetForegroundColor(Color::Black).execute_winapi()
+        }
+    }
+}
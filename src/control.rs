@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::procfs;
+
+/// The signals that can be sent from the UI, the common ones first
+#[cfg(unix)]
+pub const SIGNALS: [(&str, i32); 22] = [
+    ("SIGTERM", libc::SIGTERM),
+    ("SIGKILL", libc::SIGKILL),
+    ("SIGSTOP", libc::SIGSTOP),
+    ("SIGCONT", libc::SIGCONT),
+    ("SIGHUP", libc::SIGHUP),
+    ("SIGINT", libc::SIGINT),
+    ("SIGQUIT", libc::SIGQUIT),
+    ("SIGUSR1", libc::SIGUSR1),
+    ("SIGUSR2", libc::SIGUSR2),
+    ("SIGABRT", libc::SIGABRT),
+    ("SIGALRM", libc::SIGALRM),
+    ("SIGBUS", libc::SIGBUS),
+    ("SIGCHLD", libc::SIGCHLD),
+    ("SIGFPE", libc::SIGFPE),
+    ("SIGILL", libc::SIGILL),
+    ("SIGPIPE", libc::SIGPIPE),
+    ("SIGPROF", libc::SIGPROF),
+    ("SIGSEGV", libc::SIGSEGV),
+    ("SIGTRAP", libc::SIGTRAP),
+    ("SIGTSTP", libc::SIGTSTP),
+    ("SIGTTIN", libc::SIGTTIN),
+    ("SIGWINCH", libc::SIGWINCH),
+];
+
+#[cfg(not(unix))]
+pub const SIGNALS: [(&str, i32); 0] = [];
+
+/// Sends the signal to the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `signal` - The number of the signal
+#[cfg(unix)]
+pub fn send_signal(pid: u32, signal: i32) -> io::Result<()> {
+    let pid = checked_pid(pid)?;
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Sends the signal to the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `signal` - The number of the signal
+#[cfg(not(unix))]
+pub fn send_signal(_pid: u32, _signal: i32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other, "signals are not supported on this platform"))
+}
+
+/// Returns the pid as a `pid_t`, 0 and the values that don't fit would target the
+/// process group or every process instead of a single process
+/// ### Parameters
+/// * `pid` - The id of the process
+#[cfg(unix)]
+fn checked_pid(pid: u32) -> io::Result<libc::pid_t> {
+    if pid == 0 || pid > i32::MAX as u32 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid process id {}", pid)));
+    }
+    Ok(pid as libc::pid_t)
+}
+
+/// Returns the name of the signal, or its number for signals without a name
+/// ### Parameters
+/// * `signal` - The number of the signal
+pub fn signal_name(signal: i32) -> String {
+    match SIGNALS.iter().find(|(_, number)| *number == signal) {
+        Some((name, _)) => name.to_string(),
+        None => format!("signal {}", signal),
+    }
+}
+
+/// The I/O scheduling classes of `ioprio_set`, the index is the class number
+pub const IO_CLASSES: [&str; 4] = ["none", "realtime", "best-effort", "idle"];
+
+/// The lowest and highest nice values
+pub const NICE_RANGE: (i32, i32) = (-20, 19);
+
+/// Reads the nice value of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+#[cfg(unix)]
+pub fn get_nice(pid: u32) -> io::Result<i32> {
+    // -1 is a valid nice value so errors are told apart by errno
+    unsafe { *errno_location() = 0 };
+    let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+    if nice == -1 && unsafe { *errno_location() } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(nice)
+}
+
+/// Changes the nice value of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `nice` - The new nice value
+#[cfg(unix)]
+pub fn set_nice(pid: u32, nice: i32) -> io::Result<()> {
+    let pid = checked_pid(pid)?;
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__errno_location()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__error()
+}
+
+/// Reads the nice value of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+#[cfg(not(unix))]
+pub fn get_nice(_pid: u32) -> io::Result<i32> {
+    Err(io::Error::new(io::ErrorKind::Other, "nice values are not supported on this platform"))
+}
+
+/// Changes the nice value of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `nice` - The new nice value
+#[cfg(not(unix))]
+pub fn set_nice(_pid: u32, _nice: i32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other, "nice values are not supported on this platform"))
+}
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+/// Packs the I/O scheduling class and level into the value of `ioprio_set`
+/// ### Parameters
+/// * `class` - The index of the class in `IO_CLASSES`
+/// * `level` - The priority level within the class
+#[cfg(target_os = "linux")]
+fn encode_io_priority(class: u32, level: u32) -> u32 {
+    (class << IOPRIO_CLASS_SHIFT) | level
+}
+
+/// Unpacks the value of `ioprio_get` into the I/O scheduling class and level
+/// ### Parameters
+/// * `priority` - The value returned by `ioprio_get`
+#[cfg(target_os = "linux")]
+fn decode_io_priority(priority: u32) -> (u32, u32) {
+    (priority >> IOPRIO_CLASS_SHIFT, priority & ((1 << IOPRIO_CLASS_SHIFT) - 1))
+}
+
+/// Reads the I/O scheduling class and level of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+#[cfg(target_os = "linux")]
+pub fn get_io_priority(pid: u32) -> io::Result<(u32, u32)> {
+    let priority = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid as libc::c_int) };
+    if priority < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(decode_io_priority(priority as u32))
+}
+
+/// Changes the I/O scheduling class and level of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `class` - The index of the class in `IO_CLASSES`
+/// * `level` - The priority level within the class, 0 being the highest and 7 the lowest
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(pid: u32, class: u32, level: u32) -> io::Result<()> {
+    let pid = checked_pid(pid)?;
+    let priority = encode_io_priority(class, level);
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid as libc::c_int, priority as libc::c_int) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Reads the I/O scheduling class and level of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+#[cfg(not(target_os = "linux"))]
+pub fn get_io_priority(_pid: u32) -> io::Result<(u32, u32)> {
+    Err(io::Error::new(io::ErrorKind::Other, "I/O priorities are not supported on this platform"))
+}
+
+/// Changes the I/O scheduling class and level of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `class` - The index of the class in `IO_CLASSES`
+/// * `level` - The priority level within the class, 0 being the highest and 7 the lowest
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_pid: u32, _class: u32, _level: u32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other, "I/O priorities are not supported on this platform"))
+}
+
+/// Formats the I/O priority like `ionice` does
+/// ### Parameters
+/// * `priority` - The I/O scheduling class and level
+pub fn io_priority_name(priority: (u32, u32)) -> String {
+    match priority.0 {
+        0 => String::from("none"),
+        3 => String::from("idle"),
+        class => format!("{}: prio {}", IO_CLASSES.get(class as usize).unwrap_or(&"unknown"), priority.1),
+    }
+}
+
+/// Collects the ids of the process and all of its current descendants from `/proc`
+/// ### Parameters
+/// * `pid` - The id of the process at the top of the subtree
+pub fn subtree_pids(pid: u32) -> Vec<u32> {
+    let parents: Vec<(u32, u32)> = procfs::list_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|child| procfs::read_stat(child).ok().map(|stat| (child, stat.ppid)))
+        .collect();
+    collect_subtree(pid, &parents)
+}
+
+/// Collects the ids of the process and all of its descendants, breadth first
+/// ### Parameters
+/// * `pid` - The id of the process at the top of the subtree
+/// * `parents` - The id and the parent id of every process
+fn collect_subtree(pid: u32, parents: &[(u32, u32)]) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (child, ppid) in parents {
+        if ppid != child {
+            children.entry(*ppid).or_default().push(*child);
+        }
+    }
+    let mut pids: Vec<u32> = vec![pid];
+    let mut index = 0;
+    while index < pids.len() {
+        if let Some(kids) = children.get(&pids[index]) {
+            for kid in kids {
+                if !pids.contains(kid) {
+                    pids.push(*kid);
+                }
+            }
+        }
+        index += 1;
+    }
+    pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_the_pids_that_target_several_processes() {
+        assert_eq!(checked_pid(1).unwrap(), 1);
+        assert_eq!(checked_pid(i32::MAX as u32).unwrap(), i32::MAX);
+        for pid in [0, i32::MAX as u32 + 1, u32::MAX].iter() {
+            assert_eq!(checked_pid(*pid).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(send_signal(*pid, 0).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn packs_the_io_class_above_the_level() {
+        assert_eq!(encode_io_priority(2, 4), 0x4004);
+        assert_eq!(encode_io_priority(1, 0), 0x2000);
+        assert_eq!(encode_io_priority(3, 0), 0x6000);
+        for class in 0..IO_CLASSES.len() as u32 {
+            for level in 0..8 {
+                assert_eq!(decode_io_priority(encode_io_priority(class, level)), (class, level));
+            }
+        }
+    }
+
+    #[test]
+    fn collects_the_descendants_breadth_first() {
+        // 5 and 6 are each other's parent, 1 is its own parent like the swapper
+        let parents = [(1, 1), (2, 1), (3, 2), (4, 1), (5, 6), (6, 5), (7, 3), (8, 9)];
+        assert_eq!(collect_subtree(1, &parents), vec![1, 2, 4, 3, 7]);
+        assert_eq!(collect_subtree(3, &parents), vec![3, 7]);
+        assert_eq!(collect_subtree(5, &parents), vec![5, 6]);
+        assert_eq!(collect_subtree(42, &parents), vec![42]);
+    }
+}
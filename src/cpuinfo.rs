@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::sysfs::{read_string, read_value};
+
+/// The sysfs directory with the CPU cores
+pub const CPU_DEVICES: &str = "/sys/devices/system/cpu";
+
+/// The procfs file with the CPU model and the per core details
+pub const PROC_CPUINFO: &str = "/proc/cpuinfo";
+
+/// Contains the topology and frequency of a single logical CPU core
+/// ### Fields
+/// * `id` - The number of the core, the `N` of `cpuN`
+/// * `online` - Whether the core is online, offline cores have no topology or frequency
+/// * `socket` - The physical package the core is in
+/// * `core` - The physical core of the package, SMT siblings share it
+/// * `siblings` - The logical cores sharing the physical core, including this one
+/// * `node` - The NUMA node of the core
+/// * `frequency` - The current frequency in MHz
+/// * `min_frequency` - The lowest frequency the governor may choose in MHz
+/// * `max_frequency` - The highest frequency the governor may choose in MHz
+/// * `governor` - The cpufreq scaling governor, such as `schedutil` or `performance`
+pub struct CoreInfo {
+    pub id: usize,
+    pub online: bool,
+    pub socket: Option<u32>,
+    pub core: Option<u32>,
+    pub siblings: Vec<usize>,
+    pub node: Option<u32>,
+    pub frequency: Option<f32>,
+    pub min_frequency: Option<f32>,
+    pub max_frequency: Option<f32>,
+    pub governor: Option<String>,
+}
+
+/// Contains a kind of CPU cache, such as the level 1 data caches of every core
+/// ### Fields
+/// * `level` - The level of the cache
+/// * `kind` - What the cache holds, `Data`, `Instruction` or `Unified`
+/// * `size` - The size of a single cache in bytes
+/// * `instances` - The count of these caches in the system
+/// * `shared_by` - The count of logical cores sharing a single cache
+pub struct Cache {
+    pub level: u32,
+    pub kind: String,
+    pub size: u64,
+    pub instances: usize,
+    pub shared_by: usize,
+}
+
+impl Cache {
+    /// Returns the short name of the cache, such as `L1d`, `L1i` or `L2`
+    pub fn name(&self) -> String {
+        match self.kind.as_str() {
+            "Data" => format!("L{}d", self.level),
+            "Instruction" => format!("L{}i", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+}
+
+/// Contains the model, topology, caches and frequencies of the system's CPUs
+/// ### Fields
+/// * `model` - The model name of the CPU
+/// * `vendor` - The vendor of the CPU
+/// * `driver` - The cpufreq scaling driver, such as `intel_pstate` or `acpi-cpufreq`
+/// * `sockets` - The count of physical packages
+/// * `physical_cores` - The count of physical cores
+/// * `nodes` - The count of NUMA nodes, 0 when the kernel has no NUMA support
+/// * `cores` - The logical cores in id order
+/// * `caches` - The caches from the lowest level to the highest
+pub struct CpuInfo {
+    pub model: Option<String>,
+    pub vendor: Option<String>,
+    pub driver: Option<String>,
+    pub sockets: usize,
+    pub physical_cores: usize,
+    pub nodes: usize,
+    pub cores: Vec<CoreInfo>,
+    pub caches: Vec<Cache>,
+}
+
+impl CpuInfo {
+    /// Returns the online cores, in the order of the per core lines of `/proc/stat`
+    pub fn online(&self) -> impl Iterator<Item = &CoreInfo> {
+        self.cores.iter().filter(|core| core.online)
+    }
+}
+
+/// Reads the CPU details from sysfs, the details sysfs doesn't have, like the model
+/// or the frequency in virtual machines without cpufreq, are taken from `/proc/cpuinfo`
+/// ### Parameters
+/// * `root` - The CPU device directory, `CPU_DEVICES` outside of tests
+/// * `cpuinfo` - The cpuinfo file, `PROC_CPUINFO` outside of tests
+pub fn read_cpu_info(root: &Path, cpuinfo: &Path) -> CpuInfo {
+    let processors = parse_cpuinfo(&fs::read_to_string(cpuinfo).unwrap_or_default());
+    let mut ids: Vec<usize> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("cpu")?.parse().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if ids.is_empty() {
+        // Without sysfs every processor in cpuinfo is online
+        ids = processors.keys().cloned().collect();
+    }
+    ids.sort_unstable();
+    let cores: Vec<CoreInfo> = ids.iter().map(|id| read_core(root, *id, processors.get(id))).collect();
+    let first = processors.keys().min().and_then(|id| processors.get(id));
+    let field = |names: &[&str]| -> Option<String> {
+        names.iter().find_map(|name| first.and_then(|fields| fields.get(*name)).cloned())
+    };
+    let online: Vec<&CoreInfo> = cores.iter().filter(|core| core.online).collect();
+    CpuInfo {
+        // ARM and MIPS name the model differently than x86
+        model: field(&["model name", "Processor", "cpu model", "Hardware"]),
+        vendor: field(&["vendor_id", "vendor", "CPU implementer"]),
+        driver: read_string(&root.join("cpu0/cpufreq/scaling_driver")),
+        sockets: online.iter().filter_map(|core| core.socket).collect::<HashSet<u32>>().len().max(1),
+        physical_cores: online.iter().map(|core| (core.socket, core.core.map_or(core.id as u32, |id| id))).collect::<HashSet<_>>().len(),
+        nodes: online.iter().filter_map(|core| core.node).collect::<HashSet<u32>>().len(),
+        caches: read_caches(root, &online),
+        cores,
+    }
+}
+
+/// Reads the topology and frequency of a single core
+/// ### Parameters
+/// * `root` - The CPU device directory
+/// * `id` - The number of the core
+/// * `processor` - The fields of the core in `/proc/cpuinfo`, if it is there
+fn read_core(root: &Path, id: usize, processor: Option<&HashMap<String, String>>) -> CoreInfo {
+    let directory = root.join(format!("cpu{}", id));
+    let value = |path: &str| read_value::<u32>(&directory.join(path));
+    let processor_value = |name: &str| processor.and_then(|fields| fields.get(name)).and_then(|value| value.parse::<u32>().ok());
+    // The boot core usually can't be taken offline so it has no online file
+    let online = read_string(&directory.join("online")).map_or(true, |online| online == "1");
+    // The frequencies are in kHz
+    let frequency = |names: &[&str]| -> Option<f32> {
+        names.iter().find_map(|name| value(&format!("cpufreq/{}", name))).map(|khz| khz as f32 / 1000_f32)
+    };
+    let node = fs::read_dir(&directory).ok().and_then(|entries| {
+        entries.filter_map(|entry| entry.ok()).find_map(|entry| entry.file_name().to_str()?.strip_prefix("node")?.parse().ok())
+    });
+    CoreInfo {
+        id,
+        online,
+        socket: value("topology/physical_package_id").or_else(|| processor_value("physical id")),
+        core: value("topology/core_id").or_else(|| processor_value("core id")),
+        siblings: read_string(&directory.join("topology/thread_siblings_list")).map_or_else(|| vec![id], |list| parse_cpu_list(&list)),
+        node,
+        frequency: frequency(&["scaling_cur_freq", "cpuinfo_cur_freq"])
+            .or_else(|| processor.and_then(|fields| fields.get("cpu MHz")).and_then(|mhz| mhz.parse().ok())),
+        min_frequency: frequency(&["scaling_min_freq", "cpuinfo_min_freq"]),
+        max_frequency: frequency(&["scaling_max_freq", "cpuinfo_max_freq"]),
+        governor: read_string(&directory.join("cpufreq/scaling_governor")),
+    }
+}
+
+/// Reads the caches of the online cores, the caches with the same level, kind and
+/// sharing cores are the same cache
+/// ### Parameters
+/// * `root` - The CPU device directory
+/// * `online` - The online cores
+fn read_caches(root: &Path, online: &[&CoreInfo]) -> Vec<Cache> {
+    let mut seen: HashSet<(u32, String, String)> = HashSet::new();
+    let mut caches: Vec<Cache> = Vec::new();
+    for core in online {
+        let directory = root.join(format!("cpu{}/cache", core.id));
+        let mut indexes: Vec<String> = match fs::read_dir(&directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("index"))
+                .collect(),
+            Err(_) => continue,
+        };
+        indexes.sort();
+        for index in indexes {
+            let attribute = |name: &str| read_string(&directory.join(&index).join(name));
+            let (level, kind, size) = match (attribute("level").and_then(|level| level.parse().ok()), attribute("type"), attribute("size")) {
+                (Some(level), Some(kind), Some(size)) => (level, kind, parse_cache_size(&size)),
+                _ => continue,
+            };
+            let shared = attribute("shared_cpu_list").unwrap_or_else(|| core.id.to_string());
+            if !seen.insert((level, kind.clone(), shared.clone())) {
+                continue;
+            }
+            match caches.iter_mut().find(|cache| cache.level == level && cache.kind == kind) {
+                Some(cache) => cache.instances += 1,
+                None => caches.push(Cache {
+                    level,
+                    kind,
+                    size,
+                    instances: 1,
+                    shared_by: parse_cpu_list(&shared).len().max(1),
+                }),
+            }
+        }
+    }
+    caches.sort_by(|a, b| a.level.cmp(&b.level).then_with(|| a.kind.cmp(&b.kind)));
+    caches
+}
+
+/// Parses the `/proc/cpuinfo` blocks into the fields of every processor by its number
+/// ### Parameters
+/// * `content` - The content of `/proc/cpuinfo`
+fn parse_cpuinfo(content: &str) -> HashMap<usize, HashMap<String, String>> {
+    let mut processors: HashMap<usize, HashMap<String, String>> = HashMap::new();
+    // ARM lists the model after the processors in a block of its own
+    let mut shared: HashMap<String, String> = HashMap::new();
+    for block in content.split("\n\n") {
+        let fields: HashMap<String, String> = block
+            .lines()
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect();
+        match fields.get("processor").and_then(|id| id.parse().ok()) {
+            Some(id) => {
+                processors.insert(id, fields);
+            }
+            None => shared.extend(fields),
+        }
+    }
+    for fields in processors.values_mut() {
+        for (name, value) in &shared {
+            fields.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    processors
+}
+
+/// Parses a kernel CPU list such as `0-3,8,10-11`
+/// ### Parameters
+/// * `list` - The CPU list
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus: Vec<usize> = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => cpus.extend(range.parse::<usize>().ok()),
+        }
+    }
+    cpus
+}
+
+/// Parses a cache size such as `32K` or `8M` into bytes
+/// ### Parameters
+/// * `size` - The cache size
+fn parse_cache_size(size: &str) -> u64 {
+    let (number, multiplier) = match size.chars().last() {
+        Some('K') => (&size[..size.len() - 1], 1024),
+        Some('M') => (&size[..size.len() - 1], 1024 * 1024),
+        Some('G') => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    number.parse::<u64>().unwrap_or(0) * multiplier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+
+    /// Creates a fake sysfs with an empty CPU device directory next to the cpuinfo file
+    fn fake_sysfs(name: &str) -> FakeSysfs {
+        let sysfs = FakeSysfs::new(&format!("cpuinfo-{}", name));
+        fs::create_dir_all(sysfs.root.join("cpu")).unwrap();
+        sysfs
+    }
+
+    fn read(sysfs: &FakeSysfs) -> CpuInfo {
+        read_cpu_info(&sysfs.root.join("cpu"), &sysfs.root.join("cpuinfo"))
+    }
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), vec![5]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn reads_an_smt_system_with_cpufreq() {
+        let sysfs = fake_sysfs("smt");
+        sysfs.write("cpuinfo", "processor\t: 0\nvendor_id\t: AuthenticAMD\nmodel name\t: AMD Ryzen 5 5600X\n\nprocessor\t: 1\nmodel name\t: AMD Ryzen 5 5600X\n");
+        sysfs.write("cpu/cpu0/cpufreq/scaling_driver", "acpi-cpufreq");
+        for (id, core) in [(0, 0), (1, 0), (2, 1), (3, 1)].iter() {
+            let cpu = format!("cpu/cpu{}", id);
+            sysfs.write(&format!("{}/topology/physical_package_id", cpu), "0");
+            sysfs.write(&format!("{}/topology/core_id", cpu), &core.to_string());
+            sysfs.write(&format!("{}/topology/thread_siblings_list", cpu), &format!("{}-{}", core * 2, core * 2 + 1));
+            sysfs.write(&format!("{}/node0/cpumap", cpu), "f");
+            sysfs.write(&format!("{}/cpufreq/scaling_cur_freq", cpu), "3700000");
+            sysfs.write(&format!("{}/cpufreq/scaling_min_freq", cpu), "2200000");
+            sysfs.write(&format!("{}/cpufreq/scaling_max_freq", cpu), "4650000");
+            sysfs.write(&format!("{}/cpufreq/scaling_governor", cpu), "schedutil");
+            for (index, level, kind, size, shared) in [
+                (0, 1, "Data", "32K", id.to_string()),
+                (1, 1, "Instruction", "32K", id.to_string()),
+                (2, 2, "Unified", "512K", format!("{}-{}", core * 2, core * 2 + 1)),
+                (3, 3, "Unified", "32768K", String::from("0-3")),
+            ]
+            .iter()
+            {
+                let cache = format!("{}/cache/index{}", cpu, index);
+                sysfs.write(&format!("{}/level", cache), &level.to_string());
+                sysfs.write(&format!("{}/type", cache), kind);
+                sysfs.write(&format!("{}/size", cache), size);
+                sysfs.write(&format!("{}/shared_cpu_list", cache), shared);
+            }
+        }
+        sysfs.write("cpu/cpu3/online", "1");
+        sysfs.write("cpu/cpufreq/policy0/scaling_governor", "schedutil");
+
+        let info = read(&sysfs);
+        assert_eq!(info.model.as_deref(), Some("AMD Ryzen 5 5600X"));
+        assert_eq!(info.vendor.as_deref(), Some("AuthenticAMD"));
+        assert_eq!(info.driver.as_deref(), Some("acpi-cpufreq"));
+        assert_eq!((info.sockets, info.physical_cores, info.nodes), (1, 2, 1));
+        assert_eq!(info.cores.len(), 4);
+        let core = &info.cores[3];
+        assert!(core.online);
+        assert_eq!((core.socket, core.core, core.node), (Some(0), Some(1), Some(0)));
+        assert_eq!(core.siblings, vec![2, 3]);
+        assert_eq!(core.frequency, Some(3700_f32));
+        assert_eq!(core.min_frequency, Some(2200_f32));
+        assert_eq!(core.max_frequency, Some(4650_f32));
+        assert_eq!(core.governor.as_deref(), Some("schedutil"));
+        let caches: Vec<(String, u64, usize, usize)> =
+            info.caches.iter().map(|cache| (cache.name(), cache.size, cache.instances, cache.shared_by)).collect();
+        assert_eq!(
+            caches,
+            vec![
+                (String::from("L1d"), 32 * 1024, 4, 1),
+                (String::from("L1i"), 32 * 1024, 4, 1),
+                (String::from("L2"), 512 * 1024, 2, 2),
+                (String::from("L3"), 32 * 1024 * 1024, 1, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn offline_cores_are_left_out_of_the_topology() {
+        let sysfs = fake_sysfs("offline");
+        sysfs.write("cpu/cpu0/topology/core_id", "0");
+        sysfs.write("cpu/cpu1/topology/core_id", "1");
+        sysfs.write("cpu/cpu2/online", "0");
+        let info = read(&sysfs);
+        assert_eq!(info.cores.len(), 3);
+        assert!(!info.cores[2].online);
+        assert_eq!(info.online().map(|core| core.id).collect::<Vec<usize>>(), vec![0, 1]);
+        assert_eq!(info.physical_cores, 2);
+        assert_eq!(info.nodes, 0);
+    }
+
+    #[test]
+    fn falls_back_to_cpuinfo_without_sysfs() {
+        let sysfs = fake_sysfs("cpuinfo");
+        sysfs.write(
+            "cpuinfo",
+            "processor\t: 0\ncpu MHz\t\t: 2100.000\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+             processor\t: 1\ncpu MHz\t\t: 2400.500\nphysical id\t: 1\ncore id\t\t: 0\n\n\
+             Hardware\t: BCM2835\n",
+        );
+        let info = read(&sysfs);
+        assert_eq!(info.model.as_deref(), Some("BCM2835"));
+        assert_eq!(info.cores.len(), 2);
+        assert_eq!(info.cores[1].frequency, Some(2400.5_f32));
+        assert_eq!(info.cores[1].max_frequency, None);
+        assert_eq!((info.sockets, info.physical_cores), (2, 2));
+        assert!(info.caches.is_empty());
+    }
+}
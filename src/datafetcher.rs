@@ -1,61 +1,148 @@
 extern crate systemstat;
 
-use systemstat::{saturating_sub_bytes, Platform, System, LoadAverage, PlatformMemory, Filesystem, NetworkAddrs };
+use systemstat::{Platform, System, LoadAverage, PlatformMemory, Filesystem, NetworkAddrs, DelayedMeasurement };
 
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::error::Error;
 use std::vec::Vec;
 
+use crate::cpuinfo::{self, CpuInfo};
+use crate::history::History;
+use crate::metrics;
+use crate::power::{self, PowerSupply};
+use crate::procfs;
+use crate::push::Pusher;
+use crate::record::Recorder;
+use crate::sensors::{self, Sensor};
+
 
 /// Contains the information about the system
 /// ### Fields
 /// * `cpu` - The system's CPU data
+/// * `cpu_info` - The system's CPU model, topology, caches and frequencies
 /// * `ram` - The system's memory data
 /// * `disk` - The system's disk data
+/// * `diskio` - The system's block device I/O data
 /// * `network` - The system's network data
+/// * `processes` - The system's process data
+/// * `sensors` - The system's hardware sensor data
+/// * `power` - The system's battery and AC adapter data
+/// * `detail` - The detailed information of the process chosen in the UI, if any
+/// * `threads` - The threads of the process chosen in the UI, if any
+/// * `history` - The recent usage of the CPU, memory and network for the graphs
+/// * `record_error` - Why the latest row couldn't be recorded, if it couldn't
+/// * `push_error` - Why the latest push failed, if the endpoint is unreachable
+/// * `fetch_error` - Why the latest fetch failed, the other fields are from the last one that succeeded
 pub struct SystemData {
     pub cpu: CPUData,
+    pub cpu_info: CpuInfo,
     pub ram: RAMData,
     pub disk: DiskData,
+    pub diskio: DiskIOData,
     pub network: NetworkData,
+    pub processes: ProcessData,
+    pub sensors: SensorData,
+    pub power: PowerData,
+    pub detail: Option<ProcessDetail>,
+    pub threads: Option<ThreadData>,
+    pub history: HistoryData,
+    pub record_error: Option<String>,
+    pub push_error: Option<String>,
+    pub fetch_error: Option<String>,
     pub uptime: Duration,
 }
 
+/// Contains the fractions of time a CPU core spent in each state, they add up to 1.0
+/// ### Fields
+/// * `user` - The time spent running user space tasks
+/// * `nice` - The time spent running niced user space tasks
+/// * `system` - The time spent in the kernel
+/// * `interrupt` - The time spent serving hardware interrupts
+/// * `idle` - The time spent idle
+/// * `iowait` - The time spent idle while there was I/O in flight
+/// * `softirq` - The time spent serving software interrupts
+/// * `steal` - The time the hypervisor gave to other virtual machines
+/// * `guest` - The time spent running virtual machines
+/// * `guest_nice` - The time spent running niced virtual machines
 pub struct CPULoad {
     pub user: f32,
     pub nice: f32,
     pub system: f32,
     pub interrupt: f32,
     pub idle: f32,
+    pub iowait: f32,
+    pub softirq: f32,
+    pub steal: f32,
+    pub guest: f32,
+    pub guest_nice: f32,
 }
 
 /// Contains the information about the system's CPU
 /// ### Fields
 /// * `count` - The system's CPU core count
-/// * `load` - The system's CPU load per core
-/// * `load_average` - The system's CPU load average
-/// * `temp` - The system's CPU temperature per core
+/// * `load` - The system's CPU load per core as fractions of the time
+/// * `usage` - The system's CPU usage percentage per core
+/// * `total` - The system's average CPU usage percentage
+/// * `load_average` - The system's recent load averages, the oldest first and the current one last
+/// * `tasks_running` - The count of runnable tasks
+/// * `tasks_total` - The count of all tasks
+/// * `temp` - The system's CPU temperatures in °C, the package and per core readings the sensors report
 pub struct CPUData {
     pub count: usize,
     pub load: Vec<CPULoad>,
+    pub usage: Vec<f32>,
+    pub total: f32,
     pub load_average: Vec<LoadAverage>,
+    pub tasks_running: u64,
+    pub tasks_total: u64,
     pub temp: Vec<f32>,
 }
 
 /// Contains the information about the system's RAM
 /// ### Fields
 /// * `total` - The system's total RAM
-/// * `used` - The system's used RAM
-/// * `free` - The system's free RAM
+/// * `used` - The system's used RAM, everything except the available memory
+/// * `free` - The system's free RAM, the memory that isn't used at all, not even as cache
 /// * `percentage` - The system's used RAM percentage
+/// * `available` - The memory available for new allocations without swapping
+/// * `buffers` - The memory used by block device buffers
+/// * `cached` - The memory used by the page cache, including shared memory
+/// * `shared` - The memory used by shared memory and tmpfs
+/// * `slab_reclaimable` - The kernel slab memory that can be reclaimed, such as dentry caches
+/// * `slab_unreclaimable` - The kernel slab memory that can't be reclaimed
+/// * `dirty` - The memory waiting to be written back to disk
+/// * `writeback` - The memory being written back to disk
+/// * `swap_total` - The system's total swap space
+/// * `swap_free` - The system's free swap space
+/// * `swap_cached` - The swapped out memory that is also in RAM
+/// * `hugepages_total` - The count of huge pages in the pool
+/// * `hugepages_free` - The count of unused huge pages in the pool
+/// * `hugepage_size` - The size of a huge page
 /// * `platform` - The system's RAM platform specific data
 pub struct RAMData {
     pub total: u64,
     pub used: u64,
     pub free: u64,
     pub percentage: f32,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub shared: u64,
+    pub slab_reclaimable: u64,
+    pub slab_unreclaimable: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub swap_cached: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    pub hugepage_size: u64,
     pub platform: PlatformMemory,
 }
 
@@ -66,12 +153,49 @@ pub struct RAMData {
 /// * `used` - The system's used disk space per disc
 /// * `free` - The system's free disk space per disc
 /// * `percentage` - The system's used disk space percentage per disc
+/// * `fs_mounted_from` - The device or source each filesystem is mounted from
+/// * `fs_mounted_on` - The mountpoint of each filesystem
+/// * `fs_type` - The filesystem type of each mount
+/// * `inodes_total` - The total inode count per disc
+/// * `inodes_free` - The free inode count per disc
+/// * `pseudo` - Whether the mount is a pseudo or virtual filesystem
 pub struct DiskData {
     pub count: usize,
     pub total: Vec<u64>,
     pub used: Vec<u64>,
     pub free: Vec<u64>,
     pub percentage: Vec<f32>,
+    pub fs_mounted_from: Vec<String>,
+    pub fs_mounted_on: Vec<String>,
+    pub fs_type: Vec<String>,
+    pub inodes_total: Vec<u64>,
+    pub inodes_free: Vec<u64>,
+    pub pseudo: Vec<bool>,
+}
+
+/// Contains the I/O activity of the system's block devices since the previous fetch
+/// ### Fields
+/// * `count` - The block device count
+/// * `names` - The block device names
+/// * `read_rate` - The read bytes per second per device
+/// * `write_rate` - The written bytes per second per device
+/// * `read_iops` - The completed read requests per second per device
+/// * `write_iops` - The completed write requests per second per device
+/// * `latency` - The average time in milliseconds a request took to complete per device
+/// * `queue_depth` - The average count of requests in the queue per device
+/// * `in_flight` - The count of requests currently in flight per device
+/// * `utilisation` - The percentage of time the device was busy per device
+pub struct DiskIOData {
+    pub count: usize,
+    pub names: Vec<String>,
+    pub read_rate: Vec<u64>,
+    pub write_rate: Vec<u64>,
+    pub read_iops: Vec<f32>,
+    pub write_iops: Vec<f32>,
+    pub latency: Vec<f32>,
+    pub queue_depth: Vec<f32>,
+    pub in_flight: Vec<u64>,
+    pub utilisation: Vec<f32>,
 }
 
 /// Contains the information about the system's network
@@ -81,64 +205,416 @@ pub struct DiskData {
 /// * `addresses` - The system's network interface addresses
 /// * `rx` - The system's network interface received bytes per interface
 /// * `tx` - The system's network interface transmitted bytes per interface
+/// * `rx_packets` - The received packets per interface
+/// * `tx_packets` - The transmitted packets per interface
+/// * `rx_errors` - The receive errors per interface
+/// * `tx_errors` - The transmit errors per interface
+/// * `rx_drops` - The dropped incoming packets per interface
+/// * `tx_drops` - The dropped outgoing packets per interface
+/// * `rx_rate` - The received bytes per second since the previous fetch per interface
+/// * `tx_rate` - The transmitted bytes per second since the previous fetch per interface
 pub struct NetworkData {
     pub count: usize,
     pub names: Vec<String>,
     pub adresses: Vec<Vec<NetworkAddrs>>,
     pub rx: Vec<u64>,
     pub tx: Vec<u64>,
+    pub rx_packets: Vec<u64>,
+    pub tx_packets: Vec<u64>,
+    pub rx_errors: Vec<u64>,
+    pub tx_errors: Vec<u64>,
+    pub rx_drops: Vec<u64>,
+    pub tx_drops: Vec<u64>,
+    pub rx_rate: Vec<u64>,
+    pub tx_rate: Vec<u64>,
+}
+
+/// Contains the recent samples of the metrics that are drawn as graphs, one sample per fetch
+/// ### Fields
+/// * `cpu` - The average CPU usage percentage
+/// * `cores` - The CPU usage percentage per core
+/// * `memory` - The used RAM percentage
+/// * `swap` - The used swap percentage
+/// * `network_rx` - The bytes received per second by every interface except the loopback
+/// * `network_tx` - The bytes transmitted per second by every interface except the loopback
+#[derive(Clone)]
+pub struct HistoryData {
+    pub cpu: History,
+    pub cores: Vec<History>,
+    pub memory: History,
+    pub swap: History,
+    pub network_rx: History,
+    pub network_tx: History,
+}
+
+impl HistoryData {
+    fn new() -> HistoryData {
+        HistoryData {
+            cpu: History::new(HISTORY_LENGTH),
+            cores: Vec::new(),
+            memory: History::new(HISTORY_LENGTH),
+            swap: History::new(HISTORY_LENGTH),
+            network_rx: History::new(HISTORY_LENGTH),
+            network_tx: History::new(HISTORY_LENGTH),
+        }
+    }
+
+    /// Adds the samples of a fetch to the histories
+    /// ### Parameters
+    /// * `cpu` - The fetched CPU data
+    /// * `ram` - The fetched memory data
+    /// * `network` - The fetched network data
+    fn push(&mut self, cpu: &CPUData, ram: &RAMData, network: &NetworkData) {
+        self.cpu.push(cpu.total);
+        // Cores that come online later start with an empty history
+        self.cores.resize(cpu.count, History::new(HISTORY_LENGTH));
+        for (history, usage) in self.cores.iter_mut().zip(cpu.usage.iter()) {
+            history.push(*usage);
+        }
+        self.memory.push(ram.percentage);
+        self.swap.push(metrics::percentage(ram.swap_total.saturating_sub(ram.swap_free), ram.swap_total));
+        let external = |rates: &Vec<u64>| -> f32 {
+            network.names.iter().zip(rates.iter()).filter(|(name, _)| name.as_str() != "lo").map(|(_, rate)| *rate as f32).sum()
+        };
+        self.network_rx.push(external(&network.rx_rate));
+        self.network_tx.push(external(&network.tx_rate));
+    }
+}
+
+/// Contains the readings of the system's hardware sensors
+/// ### Fields
+/// * `count` - The sensor count
+/// * `list` - The sensors grouped by their chip
+pub struct SensorData {
+    pub count: usize,
+    pub list: Vec<Sensor>,
+}
+
+/// Contains the state of the system's batteries and AC adapters
+/// ### Fields
+/// * `count` - The power supply count
+/// * `list` - The power supplies in name order
+pub struct PowerData {
+    pub count: usize,
+    pub list: Vec<PowerSupply>,
+}
+
+/// Contains the information about a single process
+/// ### Fields
+/// * `pid` - The process id
+/// * `ppid` - The process id of the parent
+/// * `name` - The executable name of the process
+/// * `user` - The name of the user owning the process
+/// * `state` - The state of the process (R, S, D, Z, T etc.)
+/// * `nice` - The nice value of the process
+/// * `cpu` - The CPU usage percentage since the previous fetch, 100% being one full core
+/// * `rss` - The resident memory of the process in bytes
+/// * `virt` - The virtual memory size of the process in bytes
+/// * `threads` - The thread count of the process
+/// * `start_time` - The start time of the process in seconds since the unix epoch
+/// * `command` - The full command line of the process
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub user: String,
+    pub state: char,
+    pub nice: i64,
+    pub cpu: f32,
+    pub rss: u64,
+    pub virt: u64,
+    pub threads: u64,
+    pub start_time: u64,
+    pub command: String,
+}
+
+#[cfg(test)]
+impl ProcessInfo {
+    /// Creates a sleeping single threaded process for the tests, named after its id
+    /// ### Parameters
+    /// * `pid` - The process id
+    /// * `ppid` - The process id of the parent
+    /// * `user` - The name of the user owning the process
+    /// * `cpu` - The CPU usage percentage
+    /// * `rss` - The resident memory in bytes
+    pub fn fixture(pid: u32, ppid: u32, user: &str, cpu: f32, rss: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            name: format!("proc{}", pid),
+            user: user.to_string(),
+            state: 'S',
+            nice: 0,
+            cpu,
+            rss,
+            virt: 0,
+            threads: 1,
+            start_time: 0,
+            command: format!("/bin/proc{}", pid),
+        }
+    }
+}
+
+/// Contains the information about the system's processes
+/// ### Fields
+/// * `count` - The process count
+/// * `list` - The processes in the order they were read
+pub struct ProcessData {
+    pub count: usize,
+    pub list: Vec<ProcessInfo>,
+}
+
+/// Contains the information about a single thread of a process
+/// ### Fields
+/// * `tid` - The thread id
+/// * `name` - The name of the thread
+/// * `state` - The state of the thread (R, S, D, Z, T etc.)
+/// * `cpu` - The CPU usage percentage since the previous fetch, 100% being one full core
+/// * `processor` - The CPU core the thread last ran on
+pub struct ThreadInfo {
+    pub tid: u32,
+    pub name: String,
+    pub state: char,
+    pub cpu: f32,
+    pub processor: u32,
+}
+
+/// Contains the threads of a single process
+/// ### Fields
+/// * `pid` - The id of the process
+/// * `count` - The thread count
+/// * `list` - The threads ordered by their id
+pub struct ThreadData {
+    pub pid: u32,
+    pub count: usize,
+    pub list: Vec<ThreadInfo>,
 }
 
-pub fn start_data_fetcher() -> Result<SystemData, Box<dyn Error>> {
+/// Contains the summary of the memory mappings of a process
+/// ### Fields
+/// * `count` - The mapping count
+/// * `total` - The total size of the mappings in bytes
+/// * `heap` - The size of the heap in bytes
+/// * `stack` - The size of the main thread's stack in bytes
+/// * `executable` - The size of the executable mappings in bytes
+/// * `anonymous` - The size of the other anonymous mappings in bytes
+/// * `files` - The mapped files and their total mapped size in bytes, largest first
+pub struct MapsSummary {
+    pub count: usize,
+    pub total: u64,
+    pub heap: u64,
+    pub stack: u64,
+    pub executable: u64,
+    pub anonymous: u64,
+    pub files: Vec<(String, u64)>,
+}
+
+/// Contains the detailed information about a single process, the sections the
+/// user isn't allowed to read are `None`
+/// ### Fields
+/// * `pid` - The process id
+/// * `ppid` - The process id of the parent
+/// * `name` - The executable name of the process
+/// * `state` - The state of the process (R, S, D, Z, T etc.)
+/// * `arguments` - The command line arguments, empty for kernel threads
+/// * `environment` - The environment variables the process was started with
+/// * `cwd` - The working directory of the process
+/// * `exe` - The path of the executable
+/// * `fds` - The open file descriptors and what they point to
+/// * `maps` - The summary of the memory mappings
+/// * `limits` - The resource limits
+/// * `cgroups` - The control groups the process belongs to
+/// * `namespaces` - The namespaces of the process and their identifiers
+pub struct ProcessDetail {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub state: char,
+    pub arguments: Vec<String>,
+    pub environment: Option<Vec<String>>,
+    pub cwd: Option<String>,
+    pub exe: Option<String>,
+    pub fds: Option<Vec<(u32, String)>>,
+    pub maps: Option<MapsSummary>,
+    pub limits: Option<Vec<procfs::Limit>>,
+    pub cgroups: Option<Vec<String>>,
+    pub namespaces: Option<Vec<(String, String)>>,
+}
+
+/// Contains the raw counters of the previous fetch that are needed to turn
+/// cumulative counters into rates
+/// ### Fields
+/// * `last_fetch` - The time of the previous fetch
+/// * `network` - The received and transmitted bytes per interface name
+/// * `block_devices` - The cumulative I/O counters per block device name
+/// * `processes` - The start time and consumed CPU ticks per process id
+/// * `threads` - The start time and consumed CPU ticks per thread id of the chosen process
+/// * `load_average` - The load averages of the previous fetches, the oldest first
+/// * `history` - The samples of the previous fetches for the graphs
+/// * `cpu_times` - The cumulative CPU time per core from `/proc/stat`
+/// * `cpu_load` - The CPU load measurement of systemstat started at the previous fetch,
+///   used on platforms without `/proc/stat`
+pub struct FetchState {
+    last_fetch: Option<Instant>,
+    network: HashMap<String, (u64, u64)>,
+    block_devices: HashMap<String, BlockCounters>,
+    processes: HashMap<u32, (u64, u64)>,
+    threads: HashMap<u32, (u64, u64)>,
+    load_average: Vec<LoadAverage>,
+    history: HistoryData,
+    cpu_times: Option<Vec<[u64; procfs::CPU_TIME_FIELDS]>>,
+    cpu_load: Option<DelayedMeasurement<Vec<systemstat::CPULoad>>>,
+}
+
+impl FetchState {
+    fn new() -> FetchState {
+        FetchState {
+            last_fetch: None,
+            network: HashMap::new(),
+            block_devices: HashMap::new(),
+            processes: HashMap::new(),
+            threads: HashMap::new(),
+            load_average: Vec::new(),
+            history: HistoryData::new(),
+            cpu_times: None,
+            cpu_load: None,
+        }
+    }
+}
+
+/// The cumulative counters of a block device as reported by the kernel
+#[derive(Clone, Copy)]
+struct BlockCounters {
+    read_ios: u64,
+    read_sectors: u64,
+    read_ticks: u64,
+    write_ios: u64,
+    write_sectors: u64,
+    write_ticks: u64,
+    io_ticks: u64,
+    time_in_queue: u64,
+}
+
+/// The count of load averages kept in the history
+const LOAD_HISTORY: usize = 60;
+
+/// The count of samples kept for the graphs, enough to fill a wide terminal
+const HISTORY_LENGTH: usize = 600;
+
+/// The size of a sector in the kernel's block device statistics
+const SECTOR_SIZE: u64 = 512;
+
+/// Fetches the first data of the system, the CPU usage is the average since boot as
+/// there is no previous fetch to compare with, returns the state for `start_fetch`
+pub fn start_data_fetcher() -> Result<(SystemData, FetchState), Box<dyn Error>> {
+    let mut state = FetchState::new();
     // Fetch the most recent data from the system
-    let data = fetch_data()?;
-    Ok(data)
+    let data = fetch_data(&mut state, None)?;
+    Ok((data, state))
 }
 
 
-/// Starts the system data fething framework
+/// Starts the system data fething framework, every fetch compares the counters with the
+/// previous one so the fetches are scheduled on a fixed timeline instead of sleeping the
+/// interval after each fetch
 /// ### Parameters
 /// * `thr_data` - The shared data that the thread will use and update
-/// * `interval` - The interval in milliseconds between each data fetching
-pub fn start_fetch(thr_data: Arc<Mutex<SystemData>>, interval: Duration) -> Result<(), Box<dyn Error>> {
+/// * `state` - The counters of the first fetch
+/// * `interval` - The interval between each data fetching
+/// * `detail_pid` - The id of the process whose details are fetched, set by the UI
+/// * `recorder` - The recording every fetch is appended to, if any
+/// * `pusher` - The endpoint every fetch is pushed to, if any
+pub fn start_fetch(
+    thr_data: Arc<Mutex<SystemData>>,
+    state: FetchState,
+    interval: Duration,
+    detail_pid: Arc<Mutex<Option<u32>>>,
+    recorder: Option<Recorder>,
+    pusher: Option<Pusher>,
+) -> Result<(), Box<dyn Error>> {
     thread::spawn(move || {
+        let mut state = state;
+        let mut recorder = recorder;
+        let mut deadline = state.last_fetch.unwrap_or_else(Instant::now);
         loop {
+            wait_for_next_fetch(&mut deadline, interval);
+            let pid = *detail_pid.lock().unwrap();
             // Fetch the most recent data from the system
-            match fetch_data() {
-                Ok(data) => {
+            match fetch_data(&mut state, pid) {
+                Ok(mut data) => {
+                    // A failed row is reported and the next fetch tries again, a full disk
+                    // shouldn't end an overnight recording
+                    if let Some(recorder) = &mut recorder {
+                        data.record_error = recorder.record(&data).err().map(|e| e.to_string());
+                    }
+                    if let Some(pusher) = &pusher {
+                        pusher.push(&data);
+                        data.push_error = pusher.error();
+                    }
                     // Update the shared data
                     let mut data_lock = thr_data.lock().unwrap(); // Lock the shared data
                     *data_lock = data; // Update the shared data
                     drop(data_lock); // Drop the lock
                 },
+                // Printing would tear the UI, the error is shown on the bottom bar instead
                 Err(e) => {
-                    println!("Error: {}", e);
+                    thr_data.lock().unwrap().fetch_error = Some(e.to_string());
                 }
             }
-            // Sleep for the interval
-            thread::sleep(interval);
         }
     });
     Ok(())
 }
 
-fn fetch_data() -> Result<SystemData, Box<dyn Error>> {
+/// Sleeps until the next fetch on the timeline of fixed intervals
+/// ### Parameters
+/// * `deadline` - The time of the previous fetch, moved to the time of the next one
+/// * `interval` - The interval between each data fetching
+pub fn wait_for_next_fetch(deadline: &mut Instant, interval: Duration) {
+    *deadline += interval;
+    let now = Instant::now();
+    if *deadline > now {
+        thread::sleep(*deadline - now);
+    } else {
+        // A fetch that took longer than the interval moves the timeline instead
+        // of starting a burst of fetches to catch up
+        *deadline = now;
+    }
+}
+
+/// Fetches the data of the system, the rates are measured since the previous fetch
+/// ### Parameters
+/// * `state` - The counters of the previous fetch, updated to the current ones
+/// * `detail_pid` - The id of the process whose details are fetched, if any
+pub fn fetch_data(state: &mut FetchState, detail_pid: Option<u32>) -> Result<SystemData, Box<dyn Error>> {
     let system = System::new();
+    // Every rate is measured over the same time between fetches
+    let now = Instant::now();
+    let elapsed = state.last_fetch.map(|last| now.duration_since(last));
+    state.last_fetch = Some(now);
+    let sensors = get_sensor_data();
+    let cpu = get_cpu_data(&system, &sensors, state)?;
+    let ram = get_ram_data(&system)?;
+    let network = get_network_data(&system, state, elapsed)?;
+    state.history.push(&cpu, &ram, &network);
     let data: SystemData = SystemData {
-        cpu: get_cpu_data(&system)?,
-        ram: get_ram_data(&system)?,
+        cpu,
+        cpu_info: cpuinfo::read_cpu_info(Path::new(cpuinfo::CPU_DEVICES), Path::new(cpuinfo::PROC_CPUINFO)),
+        ram,
         disk: get_disk_data(&system)?,
-        network: NetworkData {
-            count: 0,
-            names: Vec::new(),
-            adresses: Vec::new(),
-            rx: Vec::new(),
-            tx: Vec::new(),
-        },
+        diskio: get_diskio_data(&system, state, elapsed),
+        network,
+        processes: get_process_data(state, elapsed),
+        sensors,
+        power: get_power_data(),
+        detail: detail_pid.and_then(get_process_detail),
+        threads: detail_pid.and_then(|pid| get_thread_data(pid, state, elapsed)),
+        history: state.history.clone(),
+        record_error: None,
+        push_error: None,
+        fetch_error: None,
         uptime: system.uptime()?,
     };
-    //data.disk = get_disk_data(&mut system)?;
-    //data.network = get_network_data(&mut system)?;
     Ok(data)
 }
 
@@ -146,30 +622,97 @@ fn fetch_data() -> Result<SystemData, Box<dyn Error>> {
 /// the first index is the cpu core and the second is the exact usage
 /// ### Parameters
 /// * `system` - The reference to the System
+/// * `sensors` - The hardware sensors, the CPU temperatures are taken from them
+/// * `state` - The CPU counters of the previous fetch and the load average history, both are updated
 fn get_cpu_data(
     system: &System,
+    sensors: &SensorData,
+    state: &mut FetchState,
 ) -> Result<CPUData, Box<dyn Error>> {
-    let cpu_agg = system.cpu_load()?;
-    thread::sleep(Duration::from_secs(1));
-    let cpu = cpu_agg.done()?;
     let mut load_vec: Vec<CPULoad> = vec![];
-    for i in 0..cpu.len() {
-        load_vec.push(CPULoad {
-            user: cpu[i].user,
-            nice: cpu[i].nice,
-            system: cpu[i].system,
-            interrupt: cpu[i].interrupt,
-            idle: cpu[i].idle,
-            //platform: cpu[i].platform,
-        });
+    match procfs::read_cpu_times() {
+        // /proc/stat has the iowait, softirq, steal and guest time that systemstat leaves out
+        Ok(times) if !times.is_empty() => {
+            // The first fetch and a changed core count compare with the boot
+            let previous = state.cpu_times.take().filter(|previous| previous.len() == times.len());
+            let previous = previous.unwrap_or_else(|| vec![[0; procfs::CPU_TIME_FIELDS]; times.len()]);
+            for (previous, current) in previous.iter().zip(times.iter()) {
+                let fractions = metrics::cpu_time_fractions(previous, current);
+                load_vec.push(CPULoad {
+                    user: fractions[0],
+                    nice: fractions[1],
+                    system: fractions[2],
+                    idle: fractions[3],
+                    iowait: fractions[4],
+                    interrupt: fractions[5],
+                    softirq: fractions[6],
+                    steal: fractions[7],
+                    guest: fractions[8],
+                    guest_nice: fractions[9],
+                });
+            }
+            state.cpu_times = Some(times);
+        }
+        _ => {
+            // The measurement started at the previous fetch ends now and the next one starts
+            let cpu = match state.cpu_load.take() {
+                Some(measurement) => measurement.done()?,
+                None => Vec::new(),
+            };
+            state.cpu_load = Some(system.cpu_load()?);
+            if cpu.is_empty() {
+                // The first fetch has nothing to measure yet, its cores are shown idle
+                let count = thread::available_parallelism().map_or(1, |count| count.get());
+                for _ in 0..count {
+                    load_vec.push(CPULoad {
+                        user: 0_f32,
+                        nice: 0_f32,
+                        system: 0_f32,
+                        interrupt: 0_f32,
+                        idle: 1_f32,
+                        iowait: 0_f32,
+                        softirq: 0_f32,
+                        steal: 0_f32,
+                        guest: 0_f32,
+                        guest_nice: 0_f32,
+                    });
+                }
+            }
+            for core in &cpu {
+                load_vec.push(CPULoad {
+                    user: core.user,
+                    nice: core.nice,
+                    system: core.system,
+                    interrupt: core.interrupt,
+                    idle: core.idle,
+                    iowait: 0_f32,
+                    softirq: 0_f32,
+                    steal: 0_f32,
+                    guest: 0_f32,
+                    guest_nice: 0_f32,
+                });
+            }
+        }
+    }
+    // Platforms without /proc have the load averages but no task counts
+    let (load_average, tasks_running, tasks_total) = match procfs::read_loadavg() {
+        Ok(loadavg) => (LoadAverage { one: loadavg.one, five: loadavg.five, fifteen: loadavg.fifteen }, loadavg.running, loadavg.total),
+        Err(_) => (system.load_average()?, 0, 0),
+    };
+    state.load_average.push(load_average);
+    if state.load_average.len() > LOAD_HISTORY {
+        state.load_average.remove(0);
     }
+    let usage: Vec<f32> = load_vec.iter().map(|load| metrics::cpu_usage(load.idle, load.iowait)).collect();
     let data: CPUData = CPUData {
-        count: cpu.len(),
+        count: load_vec.len(),
         load: load_vec,
-
-        // TODO: Implement these
-        load_average: Vec::new(),
-        temp: Vec::new(),
+        total: metrics::cpu_total(&usage),
+        usage,
+        load_average: state.load_average.clone(),
+        tasks_running,
+        tasks_total,
+        temp: sensors.list.iter().filter(|sensor| sensor.is_cpu_temperature()).map(|sensor| sensor.value).collect(),
     };
     Ok(data)
 }
@@ -181,11 +724,31 @@ fn get_cpu_data(
 fn get_ram_data(system: &System) -> Result<RAMData, Box<dyn Error>> {
     match system.memory() {
         Ok(mem) => {
+            // The breakdown is only known on platforms with /proc
+            let meminfo = procfs::read_meminfo().unwrap_or_default();
+            let field = |name: &str| meminfo.get(name).cloned().unwrap_or(0);
+            let total = mem.total.as_u64();
+            let free = meminfo.get("MemFree").cloned().unwrap_or_else(|| mem.free.as_u64());
+            let used = metrics::memory_used(total, field("MemAvailable"), free);
             let data: RAMData = RAMData {
-                total: mem.total.as_u64(),
-                used: mem.total.as_u64() - mem.free.as_u64(),
-                free: mem.free.as_u64(),
-                percentage: mem.total.as_u64() as f32 - mem.free.as_u64() as f32 / mem.total.as_u64() as f32 * 100_f32,
+                total,
+                used,
+                free,
+                percentage: metrics::percentage(used, total),
+                available: field("MemAvailable"),
+                buffers: field("Buffers"),
+                cached: field("Cached"),
+                shared: field("Shmem"),
+                slab_reclaimable: field("SReclaimable"),
+                slab_unreclaimable: field("SUnreclaim"),
+                dirty: field("Dirty"),
+                writeback: field("Writeback"),
+                swap_total: field("SwapTotal"),
+                swap_free: field("SwapFree"),
+                swap_cached: field("SwapCached"),
+                hugepages_total: field("HugePages_Total"),
+                hugepages_free: field("HugePages_Free"),
+                hugepage_size: field("Hugepagesize"),
                 platform: mem.platform_memory,
             };
             Ok(data)
@@ -197,29 +760,394 @@ fn get_ram_data(system: &System) -> Result<RAMData, Box<dyn Error>> {
 fn get_disk_data(system: &System) -> Result<DiskData, Box<dyn Error>> {
     match system.mounts() {
         Ok(mounts) => {
-            let mut total: Vec<u64> = Vec::new();
-            let mut used: Vec<u64> = Vec::new();
-            let mut free: Vec<u64> = Vec::new();
-            let mut percentage: Vec<f32> = Vec::new();
+            let mut data: DiskData = DiskData {
+                count: mounts.len(),
+                total: Vec::new(),
+                used: Vec::new(),
+                free: Vec::new(),
+                percentage: Vec::new(),
+                fs_mounted_from: Vec::new(),
+                fs_mounted_on: Vec::new(),
+                fs_type: Vec::new(),
+                inodes_total: Vec::new(),
+                inodes_free: Vec::new(),
+                pseudo: Vec::new(),
+            };
             for mount in &mounts {
-                total.push(mount.total.as_u64());
-                used.push(mount.total.as_u64() - mount.avail.as_u64());
-                free.push(mount.free.as_u64());
-                percentage.push((mount.total.as_u64() - mount.avail.as_u64()) as f32 / mount.total.as_u64() as f32 * 100_f32);
+                let total = mount.total.as_u64();
+                let used = metrics::disk_used(total, mount.avail.as_u64());
+                data.total.push(total);
+                data.used.push(used);
+                data.free.push(mount.free.as_u64());
+                data.percentage.push(metrics::percentage(used, total));
+                data.fs_mounted_from.push(mount.fs_mounted_from.clone());
+                data.fs_mounted_on.push(mount.fs_mounted_on.clone());
+                data.fs_type.push(mount.fs_type.clone());
+                data.inodes_total.push(mount.files_total as u64);
+                // `files` is the used inodes, `files_avail` only counts the ones unprivileged users may take
+                data.inodes_free.push(mount.files_total.saturating_sub(mount.files) as u64);
+                data.pseudo.push(is_pseudo_filesystem(mount));
             }
-            let data: DiskData = DiskData {
-                count: mounts.len(),
-                total: total,
-                used: used,
-                free: free,
-                percentage: percentage,
+            Ok(data)
+        }
+        Err(x) => Err(Box::new(x)),
+    }
+}
+
+/// Filesystem types that don't represent real storage
+const PSEUDO_FILESYSTEMS: [&str; 27] = [
+    "proc", "sysfs", "tmpfs", "devtmpfs", "devpts", "overlay", "squashfs", "cgroup", "cgroup2",
+    "securityfs", "pstore", "debugfs", "tracefs", "configfs", "fusectl", "mqueue", "hugetlbfs",
+    "bpf", "autofs", "binfmt_misc", "efivarfs", "rpc_pipefs", "nsfs", "ramfs", "selinuxfs",
+    "nfsd", "fuse.portal",
+];
+
+/// Checks if the mount is a pseudo filesystem (proc, sysfs, tmpfs, snaps etc.)
+/// that would only flood the disk list
+/// ### Parameters
+/// * `mount` - The reference to the mounted filesystem
+fn is_pseudo_filesystem(mount: &Filesystem) -> bool {
+    PSEUDO_FILESYSTEMS.contains(&mount.fs_type.as_str())
+        || mount.fs_mounted_on.starts_with("/snap/")
+        || mount.total.as_u64() == 0
+}
+
+/// Fetches the I/O counters of every block device and turns them into throughput,
+/// IOPS, latency, queue depth and utilisation since the previous fetch,
+/// returns no devices if the platform doesn't provide the statistics
+/// ### Parameters
+/// * `system` - The reference to the System
+/// * `state` - The counters of the previous fetch, updated with the current ones
+/// * `elapsed` - The time since the previous fetch, `None` on the first fetch
+fn get_diskio_data(system: &System, state: &mut FetchState, elapsed: Option<Duration>) -> DiskIOData {
+    let mut data: DiskIOData = DiskIOData {
+        count: 0,
+        names: Vec::new(),
+        read_rate: Vec::new(),
+        write_rate: Vec::new(),
+        read_iops: Vec::new(),
+        write_iops: Vec::new(),
+        latency: Vec::new(),
+        queue_depth: Vec::new(),
+        in_flight: Vec::new(),
+        utilisation: Vec::new(),
+    };
+    let stats = match system.block_device_statistics() {
+        Ok(stats) => stats,
+        Err(_) => return data,
+    };
+    let mut counters: HashMap<String, BlockCounters> = HashMap::new();
+    let mut names: Vec<&String> = stats.keys().collect();
+    names.sort();
+    for name in names {
+        let blkstats = &stats[name];
+        // Skip devices that have never been used, such as unattached loop devices
+        if blkstats.read_ios == 0 && blkstats.write_ios == 0 {
+            continue;
+        }
+        let current = BlockCounters {
+            read_ios: blkstats.read_ios as u64,
+            read_sectors: blkstats.read_sectors as u64,
+            read_ticks: blkstats.read_ticks as u64,
+            write_ios: blkstats.write_ios as u64,
+            write_sectors: blkstats.write_sectors as u64,
+            write_ticks: blkstats.write_ticks as u64,
+            io_ticks: blkstats.io_ticks as u64,
+            time_in_queue: blkstats.time_in_queue as u64,
+        };
+        let (read_rate, write_rate, read_iops, write_iops, latency, queue_depth, utilisation) =
+            match (state.block_devices.get(name), elapsed) {
+                (Some(previous), Some(elapsed)) => {
+                    let secs = elapsed.as_secs_f64();
+                    let ios = current.read_ios.saturating_sub(previous.read_ios) + current.write_ios.saturating_sub(previous.write_ios);
+                    let ticks = current.read_ticks.saturating_sub(previous.read_ticks)
+                        + current.write_ticks.saturating_sub(previous.write_ticks);
+                    (
+                        metrics::rate(previous.read_sectors, current.read_sectors, secs) * SECTOR_SIZE as f64,
+                        metrics::rate(previous.write_sectors, current.write_sectors, secs) * SECTOR_SIZE as f64,
+                        metrics::rate(previous.read_ios, current.read_ios, secs),
+                        metrics::rate(previous.write_ios, current.write_ios, secs),
+                        metrics::average_latency(ticks, ios),
+                        // Milliseconds spent in the queue per millisecond is the average queue length
+                        metrics::rate(previous.time_in_queue, current.time_in_queue, secs) / 1000_f64,
+                        metrics::utilisation(current.io_ticks.saturating_sub(previous.io_ticks), secs),
+                    )
+                }
+                _ => (0_f64, 0_f64, 0_f64, 0_f64, 0_f32, 0_f64, 0_f32),
+            };
+        counters.insert(name.clone(), current);
+        data.names.push(name.clone());
+        data.read_rate.push(read_rate as u64);
+        data.write_rate.push(write_rate as u64);
+        data.read_iops.push(read_iops as f32);
+        data.write_iops.push(write_iops as f32);
+        data.latency.push(latency);
+        data.queue_depth.push(queue_depth as f32);
+        data.in_flight.push(blkstats.in_flight as u64);
+        data.utilisation.push(utilisation);
+    }
+    data.count = data.names.len();
+    state.block_devices = counters;
+    data
+}
+
+/// Reads the hardware sensors of the system, virtual machines usually have none
+fn get_sensor_data() -> SensorData {
+    let list = sensors::read_sensors(Path::new(sensors::SYS_CLASS));
+    SensorData {
+        count: list.len(),
+        list,
+    }
+}
+
+/// Reads the batteries and AC adapters of the system
+fn get_power_data() -> PowerData {
+    let list = power::read_power_supplies(Path::new(power::POWER_SUPPLY));
+    PowerData {
+        count: list.len(),
+        list,
+    }
+}
+
+/// Reads every process from `/proc` and computes their CPU usage since the previous fetch,
+/// returns no processes if the platform has no `/proc`
+/// ### Parameters
+/// * `state` - The CPU ticks of the previous fetch, updated with the current ones
+/// * `elapsed` - The time since the previous fetch, `None` on the first fetch
+fn get_process_data(state: &mut FetchState, elapsed: Option<Duration>) -> ProcessData {
+    let mut data: ProcessData = ProcessData {
+        count: 0,
+        list: Vec::new(),
+    };
+    let pids = match procfs::list_pids() {
+        Ok(pids) => pids,
+        Err(_) => return data,
+    };
+    let ticks_per_second = procfs::clock_ticks();
+    let page_size = procfs::page_size();
+    let boot_time = procfs::boot_time().unwrap_or(0);
+    let users = procfs::user_names();
+    let mut counters: HashMap<u32, (u64, u64)> = HashMap::new();
+    for pid in pids {
+        // Processes can exit while they are being read, those are skipped
+        let stat = match procfs::read_stat(pid) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
+        let ticks = stat.utime + stat.stime;
+        let cpu = match (state.processes.get(&pid), elapsed) {
+            // A different start time means the pid was reused by a new process
+            (Some((start, previous)), Some(elapsed)) if *start == stat.starttime => {
+                metrics::ticks_percentage(*previous, ticks, ticks_per_second, elapsed.as_secs_f64())
+            }
+            _ => 0_f32,
+        };
+        counters.insert(pid, (stat.starttime, ticks));
+        let uid: Option<u32> = procfs::read_status(pid)
+            .ok()
+            .and_then(|status| status.get("Uid").and_then(|uid| uid.split_whitespace().next().and_then(|uid| uid.parse().ok())));
+        let user = match uid {
+            Some(uid) => users.get(&uid).cloned().unwrap_or_else(|| uid.to_string()),
+            None => String::from("?"),
+        };
+        let command = match procfs::read_cmdline(pid) {
+            Ok(cmdline) if !cmdline.is_empty() => cmdline,
+            _ => format!("[{}]", stat.comm),
+        };
+        data.list.push(ProcessInfo {
+            pid,
+            ppid: stat.ppid,
+            name: stat.comm,
+            user,
+            state: stat.state,
+            nice: stat.nice,
+            cpu,
+            rss: stat.rss * page_size,
+            virt: stat.vsize,
+            threads: stat.num_threads,
+            start_time: boot_time + stat.starttime / ticks_per_second,
+            command,
+        });
+    }
+    data.count = data.list.len();
+    state.processes = counters;
+    data
+}
+
+/// Reads the detailed information of the process, returns `None` if the process doesn't exist
+/// ### Parameters
+/// * `pid` - The id of the process
+fn get_process_detail(pid: u32) -> Option<ProcessDetail> {
+    let stat = procfs::read_stat(pid).ok()?;
+    let maps = procfs::read_maps(pid).ok().map(|mappings| {
+        let mut summary = MapsSummary {
+            count: mappings.len(),
+            total: 0,
+            heap: 0,
+            stack: 0,
+            executable: 0,
+            anonymous: 0,
+            files: Vec::new(),
+        };
+        let mut files: HashMap<String, u64> = HashMap::new();
+        for mapping in mappings {
+            summary.total += mapping.size;
+            if mapping.perms.contains('x') {
+                summary.executable += mapping.size;
+            }
+            match mapping.path.as_str() {
+                "[heap]" => summary.heap += mapping.size,
+                "[stack]" => summary.stack += mapping.size,
+                // Thread stacks and other private memory, [vdso] and friends are counted here too
+                "" => summary.anonymous += mapping.size,
+                path if path.starts_with('[') => summary.anonymous += mapping.size,
+                path => *files.entry(path.to_string()).or_insert(0) += mapping.size,
+            }
+        }
+        summary.files = files.into_iter().collect();
+        summary.files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        summary
+    });
+    Some(ProcessDetail {
+        pid,
+        ppid: stat.ppid,
+        name: stat.comm,
+        state: stat.state,
+        arguments: procfs::read_arguments(pid).unwrap_or_default(),
+        environment: procfs::read_environ(pid).ok(),
+        cwd: procfs::read_link(pid, "cwd").ok(),
+        exe: procfs::read_link(pid, "exe").ok(),
+        fds: procfs::read_fds(pid).ok(),
+        maps,
+        limits: procfs::read_limits(pid).ok(),
+        cgroups: procfs::read_cgroups(pid).ok(),
+        namespaces: procfs::read_namespaces(pid).ok(),
+    })
+}
+
+/// Reads the threads of the process and computes their CPU usage since the previous fetch,
+/// returns `None` if the process doesn't exist
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `state` - The CPU ticks of the threads in the previous fetch, updated with the current ones
+/// * `elapsed` - The time since the previous fetch, `None` on the first fetch
+fn get_thread_data(pid: u32, state: &mut FetchState, elapsed: Option<Duration>) -> Option<ThreadData> {
+    let tids = procfs::list_tids(pid).ok()?;
+    let ticks_per_second = procfs::clock_ticks();
+    let mut data: ThreadData = ThreadData {
+        pid,
+        count: 0,
+        list: Vec::new(),
+    };
+    let mut counters: HashMap<u32, (u64, u64)> = HashMap::new();
+    for tid in tids {
+        // Threads can exit while they are being read, those are skipped
+        let task = match procfs::read_task_stat(pid, tid) {
+            Ok(task) => task,
+            Err(_) => continue,
+        };
+        let ticks = task.utime + task.stime;
+        let cpu = match (state.threads.get(&tid), elapsed) {
+            (Some((start, previous)), Some(elapsed)) if *start == task.starttime => {
+                metrics::ticks_percentage(*previous, ticks, ticks_per_second, elapsed.as_secs_f64())
+            }
+            _ => 0_f32,
+        };
+        counters.insert(tid, (task.starttime, ticks));
+        data.list.push(ThreadInfo {
+            tid,
+            name: task.comm,
+            state: task.state,
+            cpu,
+            processor: task.processor,
+        });
+    }
+    data.count = data.list.len();
+    state.threads = counters;
+    Some(data)
+}
+
+/// Fetches the counters of every network interface and computes the throughput
+/// since the previous fetch
+/// ### Parameters
+/// * `system` - The reference to the System
+/// * `state` - The counters of the previous fetch, updated with the current ones
+/// * `elapsed` - The time since the previous fetch, `None` on the first fetch
+fn get_network_data(
+    system: &System,
+    state: &mut FetchState,
+    elapsed: Option<Duration>,
+) -> Result<NetworkData, Box<dyn Error>> {
+    match system.networks() {
+        Ok(netifs) => {
+            let mut data: NetworkData = NetworkData {
+                count: netifs.len(),
+                names: Vec::new(),
+                adresses: Vec::new(),
+                rx: Vec::new(),
+                tx: Vec::new(),
+                rx_packets: Vec::new(),
+                tx_packets: Vec::new(),
+                rx_errors: Vec::new(),
+                tx_errors: Vec::new(),
+                rx_drops: Vec::new(),
+                tx_drops: Vec::new(),
+                rx_rate: Vec::new(),
+                tx_rate: Vec::new(),
             };
+            let mut counters: HashMap<String, (u64, u64)> = HashMap::new();
+            for netif in netifs.values() {
+                // Interfaces can disappear between listing and reading, report them as idle
+                let (rx, tx, rx_packets, tx_packets, rx_errors, tx_errors) = match system.network_stats(&netif.name) {
+                    Ok(stats) => (
+                        stats.rx_bytes.as_u64(),
+                        stats.tx_bytes.as_u64(),
+                        stats.rx_packets,
+                        stats.tx_packets,
+                        stats.rx_errors,
+                        stats.tx_errors,
+                    ),
+                    Err(_) => (0, 0, 0, 0, 0, 0),
+                };
+                let (rx_rate, tx_rate) = match (state.network.get(&netif.name), elapsed) {
+                    (Some((prev_rx, prev_tx)), Some(elapsed)) => {
+                        let secs = elapsed.as_secs_f64();
+                        (metrics::rate(*prev_rx, rx, secs) as u64, metrics::rate(*prev_tx, tx, secs) as u64)
+                    }
+                    _ => (0, 0),
+                };
+                counters.insert(netif.name.clone(), (rx, tx));
+                data.names.push(netif.name.clone());
+                data.adresses.push(netif.addrs.clone());
+                data.rx.push(rx);
+                data.tx.push(tx);
+                data.rx_packets.push(rx_packets);
+                data.tx_packets.push(tx_packets);
+                data.rx_errors.push(rx_errors);
+                data.tx_errors.push(tx_errors);
+                data.rx_drops.push(read_net_counter(&netif.name, "rx_dropped"));
+                data.tx_drops.push(read_net_counter(&netif.name, "tx_dropped"));
+                data.rx_rate.push(rx_rate);
+                data.tx_rate.push(tx_rate);
+            }
+            state.network = counters;
             Ok(data)
         }
         Err(x) => Err(Box::new(x)),
     }
 }
-        
+
+/// Reads a single interface statistic from sysfs, returns 0 if it is not available
+/// ### Parameters
+/// * `name` - The name of the network interface
+/// * `counter` - The name of the counter file in the interface's statistics directory
+fn read_net_counter(name: &str, counter: &str) -> u64 {
+    fs::read_to_string(format!("/sys/class/net/{}/statistics/{}", name, counter))
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
         // match sys.mounts() {
         //     Ok(mounts) => {
         //         println!("\nMounts:");
@@ -238,32 +1166,6 @@ fn get_disk_data(system: &System) -> Result<DiskData, Box<dyn Error>> {
         //     }
         //     Err(x) => println!("\nMount at /: error: {}", x)
         // }
-        // match sys.block_device_statistics() {
-        //     Ok(stats) => {
-        //         for blkstats in stats.values() {
-        //             println!("{}: {:?}", blkstats.name, blkstats);
-        //         }
-        //     }
-        //     Err(x) => println!("\nBlock statistics error: {}", x.to_string())
-        // }
-        // match sys.networks() {
-        //     Ok(netifs) => {
-        //         println!("\nNetworks:");
-        //         for netif in netifs.values() {
-        //             println!("{} ({:?})", netif.name, netif.addrs);
-        //         }
-        //     }
-        //     Err(x) => println!("\nNetworks: error: {}", x)
-        // }
-        // match sys.networks() {
-        //     Ok(netifs) => {
-        //         println!("\nNetwork interface statistics:");
-        //         for netif in netifs.values() {
-        //             println!("{} statistics: ({:?})", netif.name, sys.network_stats(&netif.name));
-        //         }
-        //     }
-        //     Err(x) => println!("\nNetworks: error: {}", x)
-        // }
         // match sys.on_ac_power() {
         //     Ok(power) => println!(", AC power: {}", power),
         //     Err(x) => println!(", AC power: error: {}", x)
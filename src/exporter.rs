@@ -0,0 +1,379 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::args::Options;
+use crate::datafetcher::{self, SystemData};
+use crate::push::Pusher;
+use crate::record::Recorder;
+use crate::sensors::{Sensor, SensorKind};
+
+/// The content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The most bytes of a request that are read, the rest is ignored
+const MAX_REQUEST: u64 = 65536;
+
+/// The most connections answered at once, the ones beyond it are closed right away
+const MAX_CONNECTIONS: usize = 8;
+
+/// Serves the metrics of the system on `/metrics` in the Prometheus text format without
+/// touching the terminal, the metrics are fetched at the refresh interval in the background
+/// and every scrape gets the latest fetch, runs until the process is stopped
+/// ### Parameters
+/// * `address` - The address to listen on, such as `127.0.0.1:9101`
+/// * `options` - The command line options
+pub fn run(address: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(address).map_err(|e| format!("Can't listen on {}: {}", address, e))?;
+    let recorder = match &options.record {
+        Some(path) => Some(Recorder::open(path.clone())?),
+        None => None,
+    };
+    let pusher = options.push.clone().map(Pusher::start);
+    let (system_data, fetch_state) = datafetcher::start_data_fetcher()?;
+    let system_data = Arc::new(Mutex::new(system_data));
+    datafetcher::start_fetch(system_data.clone(), fetch_state, options.interval, Arc::new(Mutex::new(None)), recorder, pusher)?;
+    eprintln!("rctop: serving the metrics on http://{}/metrics", listener.local_addr()?);
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // A client that gave up before it was accepted doesn't stop the server
+            Err(_) => continue,
+        };
+        if active.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+            continue;
+        }
+        active.fetch_add(1, Ordering::SeqCst);
+        let system_data = system_data.clone();
+        let active = active.clone();
+        // A slow client doesn't hold up the other scrapes, up to the connection limit
+        thread::spawn(move || {
+            let _ = handle_connection(stream, &system_data);
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    Ok(())
+}
+
+/// Answers a single HTTP request, the connection is closed afterwards
+/// ### Parameters
+/// * `stream` - The connection of the client
+/// * `system_data` - The latest fetch of the system data
+fn handle_connection(mut stream: TcpStream, system_data: &Mutex<SystemData>) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers are read so that the client isn't reset while still sending them
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") | ("HEAD", "/metrics") => {
+            let body = metrics(&system_data.lock().unwrap());
+            ("200 OK", CONTENT_TYPE, body)
+        }
+        ("GET", "/") | ("HEAD", "/") => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            String::from("<html><head><title>rctop</title></head><body><a href=\"/metrics\">Metrics</a></body></html>\n"),
+        ),
+        ("GET", _) | ("HEAD", _) => ("404 Not Found", "text/plain; charset=utf-8", String::from("Not found, the metrics are on /metrics\n")),
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", String::from("Only GET is supported\n")),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    if method != "HEAD" {
+        response += &body;
+    }
+    stream.write_all(response.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Returns the metrics of the system data in the Prometheus text exposition format, the
+/// usages are ratios from 0 to 1, the sizes are in bytes and the rates in bytes per second
+/// ### Parameters
+/// * `sys` - The system data to export
+pub fn metrics(sys: &SystemData) -> String {
+    let mut exposition = Exposition::new();
+
+    exposition.family("rctop_uptime_seconds", "gauge", "Time since the system booted");
+    exposition.sample("rctop_uptime_seconds", &[], sys.uptime.as_secs_f64());
+
+    exposition.family("rctop_cpu_usage_ratio", "gauge", "CPU usage of all cores since the previous fetch");
+    exposition.sample("rctop_cpu_usage_ratio", &[], sys.cpu.total as f64 / 100_f64);
+    exposition.family("rctop_cpu_core_usage_ratio", "gauge", "CPU usage of each core since the previous fetch");
+    for (i, usage) in sys.cpu.usage.iter().enumerate() {
+        exposition.sample("rctop_cpu_core_usage_ratio", &[("core", &i.to_string())], *usage as f64 / 100_f64);
+    }
+    exposition.family("rctop_cpu_core_mode_ratio", "gauge", "Fraction of the time each core spent in each mode since the previous fetch");
+    for (i, load) in sys.cpu.load.iter().enumerate() {
+        let core = i.to_string();
+        let modes = [
+            ("user", load.user),
+            ("nice", load.nice),
+            ("system", load.system),
+            ("irq", load.interrupt),
+            ("softirq", load.softirq),
+            ("iowait", load.iowait),
+            ("steal", load.steal),
+            ("guest", load.guest + load.guest_nice),
+            ("idle", load.idle),
+        ];
+        for (mode, fraction) in modes.iter() {
+            exposition.sample("rctop_cpu_core_mode_ratio", &[("core", &core), ("mode", mode)], *fraction as f64);
+        }
+    }
+    if let Some(load) = sys.cpu.load_average.last() {
+        for (name, help, value) in [
+            ("rctop_load1", "Load average over 1 minute", load.one),
+            ("rctop_load5", "Load average over 5 minutes", load.five),
+            ("rctop_load15", "Load average over 15 minutes", load.fifteen),
+        ]
+        .iter()
+        {
+            exposition.family(name, "gauge", help);
+            exposition.sample(name, &[], *value as f64);
+        }
+    }
+    exposition.family("rctop_tasks_running", "gauge", "Tasks that are running or ready to run");
+    exposition.sample("rctop_tasks_running", &[], sys.cpu.tasks_running as f64);
+    exposition.family("rctop_tasks", "gauge", "Tasks including the threads");
+    exposition.sample("rctop_tasks", &[], sys.cpu.tasks_total as f64);
+    exposition.family("rctop_processes", "gauge", "Processes");
+    exposition.sample("rctop_processes", &[], sys.processes.count as f64);
+
+    let ram = &sys.ram;
+    for (name, help, value) in [
+        ("rctop_memory_total_bytes", "Total memory", ram.total),
+        ("rctop_memory_used_bytes", "Memory used by processes and the kernel, without the buffers and cache", ram.used),
+        ("rctop_memory_free_bytes", "Unused memory", ram.free),
+        ("rctop_memory_available_bytes", "Memory available to new processes without swapping", ram.available),
+        ("rctop_memory_buffers_bytes", "Memory used by the block device buffers", ram.buffers),
+        ("rctop_memory_cached_bytes", "Memory used by the page cache", ram.cached),
+        ("rctop_memory_shared_bytes", "Memory used by tmpfs and shared memory", ram.shared),
+        ("rctop_swap_total_bytes", "Total swap space", ram.swap_total),
+        ("rctop_swap_used_bytes", "Used swap space", ram.swap_total.saturating_sub(ram.swap_free)),
+    ]
+    .iter()
+    {
+        exposition.family(name, "gauge", help);
+        exposition.sample(name, &[], *value as f64);
+    }
+
+    let disk = &sys.disk;
+    let filesystems: Vec<usize> = (0..disk.count).filter(|i| !disk.pseudo[*i]).collect();
+    for (name, help, values) in [
+        ("rctop_filesystem_size_bytes", "Size of the filesystem", &disk.total),
+        ("rctop_filesystem_used_bytes", "Used space of the filesystem", &disk.used),
+        ("rctop_filesystem_free_bytes", "Free space of the filesystem", &disk.free),
+        ("rctop_filesystem_inodes", "Inodes of the filesystem", &disk.inodes_total),
+        ("rctop_filesystem_inodes_free", "Free inodes of the filesystem", &disk.inodes_free),
+    ]
+    .iter()
+    {
+        exposition.family(name, "gauge", help);
+        for i in &filesystems {
+            let labels = [("mountpoint", disk.fs_mounted_on[*i].as_str()), ("device", disk.fs_mounted_from[*i].as_str()), ("fstype", disk.fs_type[*i].as_str())];
+            exposition.sample(name, &labels, values[*i] as f64);
+        }
+    }
+
+    let diskio = &sys.diskio;
+    for (name, help, values) in [
+        ("rctop_disk_read_bytes_per_second", "Bytes read from the block device per second since the previous fetch", diskio.read_rate.iter().map(|rate| *rate as f64).collect::<Vec<f64>>()),
+        ("rctop_disk_written_bytes_per_second", "Bytes written to the block device per second since the previous fetch", diskio.write_rate.iter().map(|rate| *rate as f64).collect()),
+        ("rctop_disk_utilisation_ratio", "Fraction of the time the block device was busy since the previous fetch", diskio.utilisation.iter().map(|utilisation| *utilisation as f64 / 100_f64).collect()),
+    ]
+    .iter()
+    {
+        exposition.family(name, "gauge", help);
+        for (device, value) in diskio.names.iter().zip(values.iter()) {
+            exposition.sample(name, &[("device", device)], *value);
+        }
+    }
+
+    let network = &sys.network;
+    for (name, kind, help, values) in [
+        ("rctop_network_receive_bytes_total", "counter", "Bytes received by the interface", &network.rx),
+        ("rctop_network_transmit_bytes_total", "counter", "Bytes transmitted by the interface", &network.tx),
+        ("rctop_network_receive_packets_total", "counter", "Packets received by the interface", &network.rx_packets),
+        ("rctop_network_transmit_packets_total", "counter", "Packets transmitted by the interface", &network.tx_packets),
+        ("rctop_network_receive_errors_total", "counter", "Receive errors of the interface", &network.rx_errors),
+        ("rctop_network_transmit_errors_total", "counter", "Transmit errors of the interface", &network.tx_errors),
+        ("rctop_network_receive_drops_total", "counter", "Incoming packets dropped by the interface", &network.rx_drops),
+        ("rctop_network_transmit_drops_total", "counter", "Outgoing packets dropped by the interface", &network.tx_drops),
+        ("rctop_network_receive_bytes_per_second", "gauge", "Bytes received per second since the previous fetch", &network.rx_rate),
+        ("rctop_network_transmit_bytes_per_second", "gauge", "Bytes transmitted per second since the previous fetch", &network.tx_rate),
+    ]
+    .iter()
+    {
+        exposition.family(name, kind, help);
+        for (interface, value) in network.names.iter().zip(values.iter()) {
+            exposition.sample(name, &[("interface", interface)], *value as f64);
+        }
+    }
+
+    write_sensors(&mut exposition, &sys.sensors.list);
+    exposition.text
+}
+
+/// Adds a family per sensor kind, the device label tells apart the chips with the same
+/// name, such as the `coretemp` of each socket or the `nvme` of each drive
+/// ### Parameters
+/// * `exposition` - The exposition to add to
+/// * `sensors` - The readings of the hardware sensors
+fn write_sensors(exposition: &mut Exposition, sensors: &[Sensor]) {
+    for kind in SensorKind::ALL.iter() {
+        let (name, help) = match kind {
+            SensorKind::Temperature => ("rctop_sensor_temperature_celsius", "Temperature reported by the hardware sensor"),
+            SensorKind::Fan => ("rctop_sensor_fan_rpm", "Fan speed reported by the hardware sensor"),
+            SensorKind::Voltage => ("rctop_sensor_voltage_volts", "Voltage reported by the hardware sensor"),
+            SensorKind::Current => ("rctop_sensor_current_amperes", "Current reported by the hardware sensor"),
+            SensorKind::Power => ("rctop_sensor_power_watts", "Power reported by the hardware sensor"),
+        };
+        let sensors: Vec<_> = sensors.iter().filter(|sensor| sensor.kind == *kind).collect();
+        if sensors.is_empty() {
+            continue;
+        }
+        exposition.family(name, "gauge", help);
+        for sensor in sensors {
+            exposition.sample(name, &[("chip", &sensor.chip), ("device", &sensor.device), ("sensor", &sensor.label)], sensor.value as f64);
+        }
+    }
+}
+
+/// Builds the text exposition format, a family is the HELP and TYPE lines of a metric
+/// followed by its samples
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    fn new() -> Exposition {
+        Exposition { text: String::new() }
+    }
+
+    /// Starts a metric family
+    /// ### Parameters
+    /// * `name` - The name of the metric
+    /// * `kind` - The type of the metric, such as `gauge` or `counter`
+    /// * `help` - The description of the metric
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+    }
+
+    /// Adds a sample of the current metric family
+    /// ### Parameters
+    /// * `name` - The name of the metric
+    /// * `labels` - The names and values of the labels
+    /// * `value` - The value of the sample
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.text += name;
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels.iter().map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value))).collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {}", format_value(value));
+    }
+}
+
+/// Escapes a label value, the backslashes, quotes and line breaks
+/// ### Parameters
+/// * `value` - The value of the label
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Formats a sample value, the infinities and NaN have their own spelling in the format
+/// ### Parameters
+/// * `value` - The value of the sample
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        String::from("NaN")
+    } else if value.is_infinite() {
+        String::from(if value > 0_f64 { "+Inf" } else { "-Inf" })
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_families_and_labelled_samples() {
+        let mut exposition = Exposition::new();
+        exposition.family("rctop_network_receive_bytes_total", "counter", "Bytes received by the interface");
+        exposition.sample("rctop_network_receive_bytes_total", &[("interface", "eth0")], 1024_f64);
+        exposition.sample("rctop_network_receive_bytes_total", &[("interface", "lo"), ("note", "a \"b\"\\\n")], 0.5_f64);
+        exposition.family("rctop_uptime_seconds", "gauge", "Time since\nboot");
+        exposition.sample("rctop_uptime_seconds", &[], 12_f64);
+        assert_eq!(
+            exposition.text,
+            "# HELP rctop_network_receive_bytes_total Bytes received by the interface\n\
+             # TYPE rctop_network_receive_bytes_total counter\n\
+             rctop_network_receive_bytes_total{interface=\"eth0\"} 1024\n\
+             rctop_network_receive_bytes_total{interface=\"lo\",note=\"a \\\"b\\\"\\\\\\n\"} 0.5\n\
+             # HELP rctop_uptime_seconds Time since\\nboot\n\
+             # TYPE rctop_uptime_seconds gauge\n\
+             rctop_uptime_seconds 12\n"
+        );
+    }
+
+    #[test]
+    fn labels_the_device_of_chips_with_the_same_name() {
+        let drive = |device: &str, value: f32| Sensor {
+            chip: String::from("nvme"),
+            device: device.to_string(),
+            label: String::from("Composite"),
+            kind: SensorKind::Temperature,
+            value,
+            min: None,
+            max: None,
+            crit: None,
+        };
+        let mut exposition = Exposition::new();
+        write_sensors(&mut exposition, &[drive("hwmon1", 38_f32), drive("hwmon2", 41_f32)]);
+        assert_eq!(
+            exposition.text,
+            "# HELP rctop_sensor_temperature_celsius Temperature reported by the hardware sensor\n\
+             # TYPE rctop_sensor_temperature_celsius gauge\n\
+             rctop_sensor_temperature_celsius{chip=\"nvme\",device=\"hwmon1\",sensor=\"Composite\"} 38\n\
+             rctop_sensor_temperature_celsius{chip=\"nvme\",device=\"hwmon2\",sensor=\"Composite\"} 41\n"
+        );
+    }
+
+    #[test]
+    fn spells_special_values() {
+        assert_eq!(format_value(f64::NAN), "NaN");
+        assert_eq!(format_value(f64::INFINITY), "+Inf");
+        assert_eq!(format_value(f64::NEG_INFINITY), "-Inf");
+        assert_eq!(format_value(-3_f64), "-3");
+    }
+}
@@ -0,0 +1,253 @@
+use regex::Regex;
+
+use crate::datafetcher::ProcessInfo;
+
+/// The numeric process fields that can be compared in a filter
+#[derive(Clone, Copy)]
+enum Field {
+    Cpu,
+    Memory,
+    Virtual,
+    Threads,
+    Nice,
+    Pid,
+    Ppid,
+}
+
+/// The comparison operators of the numeric predicates
+#[derive(Clone, Copy)]
+enum Operator {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// A single term of a filter expression
+enum Term {
+    /// Case insensitive substring of the name or the command line
+    Text(String),
+    /// Regular expression matched against the name and the command line
+    Pattern(Regex),
+    /// Exact user name
+    User(String),
+    /// Any of the given process states
+    State(String),
+    /// Any of the given process ids
+    Pids(Vec<u32>),
+    /// Numeric predicate such as `cpu>5`
+    Compare(Field, Operator, f64),
+    /// Negation of the inner term
+    Not(Box<Term>),
+}
+
+/// A parsed filter expression, a process matches when it matches every term
+/// ### Syntax
+/// * `text` - The name or command line contains the text, case insensitive
+/// * `re:pattern` or `/pattern/` - The name or command line matches the regular expression
+/// * `user:name` or `u:name` - The process is owned by the user
+/// * `state:RD` or `s:RD` - The process is in one of the states
+/// * `pid:1,2,3` - The process has one of the ids
+/// * `cpu>5`, `mem>=100M`, `virt<1G`, `threads>10`, `nice<0`, `pid=1`, `ppid!=2` - Numeric predicates
+/// * `!term` - The process doesn't match the term
+pub struct ProcessFilter {
+    terms: Vec<Term>,
+}
+
+impl ProcessFilter {
+    /// Parses the filter expression, returns a description of the problem for invalid ones
+    /// ### Parameters
+    /// * `expression` - The filter expression, terms separated by whitespace
+    pub fn parse(expression: &str) -> Result<ProcessFilter, String> {
+        let mut terms: Vec<Term> = Vec::new();
+        for word in expression.split_whitespace() {
+            terms.push(parse_term(word)?);
+        }
+        Ok(ProcessFilter { terms })
+    }
+
+    /// Checks if the process matches every term of the filter
+    /// ### Parameters
+    /// * `process` - The process to check
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        self.terms.iter().all(|term| term_matches(term, process))
+    }
+}
+
+/// Parses a single term of a filter expression
+/// ### Parameters
+/// * `word` - The term without surrounding whitespace
+fn parse_term(word: &str) -> Result<Term, String> {
+    if word.len() > 1 && word.starts_with('!') {
+        return Ok(Term::Not(Box::new(parse_term(&word[1..])?)));
+    }
+    if let Some(pattern) = word.strip_prefix("re:") {
+        return parse_pattern(pattern);
+    }
+    if word.len() > 2 && word.starts_with('/') && word.ends_with('/') {
+        return parse_pattern(&word[1..word.len() - 1]);
+    }
+    if let Some(user) = word.strip_prefix("user:").or_else(|| word.strip_prefix("u:")) {
+        return Ok(Term::User(user.to_string()));
+    }
+    if let Some(state) = word.strip_prefix("state:").or_else(|| word.strip_prefix("s:")) {
+        return Ok(Term::State(state.to_uppercase()));
+    }
+    if let Some(pids) = word.strip_prefix("pid:") {
+        let pids: Result<Vec<u32>, _> = pids.split(',').filter(|pid| !pid.is_empty()).map(|pid| pid.parse::<u32>()).collect();
+        return match pids {
+            Ok(pids) if !pids.is_empty() => Ok(Term::Pids(pids)),
+            _ => Err(format!("Invalid process id list in '{}'", word)),
+        };
+    }
+    if let Some(index) = word.find(['<', '>', '=', '!']) {
+        let field = match word[..index].to_lowercase().as_str() {
+            "cpu" => Some(Field::Cpu),
+            "mem" | "rss" => Some(Field::Memory),
+            "virt" => Some(Field::Virtual),
+            "threads" | "thr" => Some(Field::Threads),
+            "nice" | "ni" => Some(Field::Nice),
+            "pid" => Some(Field::Pid),
+            "ppid" => Some(Field::Ppid),
+            _ => None,
+        };
+        if let Some(field) = field {
+            let rest = &word[index..];
+            let (operator, value) = if let Some(value) = rest.strip_prefix(">=") {
+                (Operator::GreaterOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix("<=") {
+                (Operator::LessOrEqual, value)
+            } else if let Some(value) = rest.strip_prefix("!=") {
+                (Operator::NotEqual, value)
+            } else if let Some(value) = rest.strip_prefix("==") {
+                (Operator::Equal, value)
+            } else if let Some(value) = rest.strip_prefix('>') {
+                (Operator::Greater, value)
+            } else if let Some(value) = rest.strip_prefix('<') {
+                (Operator::Less, value)
+            } else if let Some(value) = rest.strip_prefix('=') {
+                (Operator::Equal, value)
+            } else {
+                return Err(format!("Invalid operator in '{}'", word));
+            };
+            let value = parse_number(value).ok_or_else(|| format!("Invalid number in '{}'", word))?;
+            return Ok(Term::Compare(field, operator, value));
+        }
+    }
+    Ok(Term::Text(word.to_lowercase()))
+}
+
+/// Compiles the regular expression of a pattern term
+/// ### Parameters
+/// * `pattern` - The regular expression
+fn parse_pattern(pattern: &str) -> Result<Term, String> {
+    match Regex::new(pattern) {
+        Ok(regex) => Ok(Term::Pattern(regex)),
+        // The regex errors span several lines with a caret under the problem
+        Err(e) => Err(format!("Invalid pattern '{}': {}", pattern, e.to_string().split_whitespace().collect::<Vec<&str>>().join(" "))),
+    }
+}
+
+/// Parses a number with an optional size suffix such as `100M` or `1.5G`
+/// ### Parameters
+/// * `value` - The number to parse
+fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim_end_matches(['B', 'b']);
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') | Some('k') => (&value[..value.len() - 1], 1024_f64),
+        Some('M') | Some('m') => (&value[..value.len() - 1], 1024_f64 * 1024_f64),
+        Some('G') | Some('g') => (&value[..value.len() - 1], 1024_f64 * 1024_f64 * 1024_f64),
+        Some('T') | Some('t') => (&value[..value.len() - 1], 1024_f64 * 1024_f64 * 1024_f64 * 1024_f64),
+        _ => (value, 1_f64),
+    };
+    number.parse::<f64>().ok().map(|number| number * multiplier)
+}
+
+/// Checks if the process matches the term
+/// ### Parameters
+/// * `term` - The term to check
+/// * `process` - The process to check
+fn term_matches(term: &Term, process: &ProcessInfo) -> bool {
+    match term {
+        Term::Text(text) => {
+            process.name.to_lowercase().contains(text.as_str()) || process.command.to_lowercase().contains(text.as_str())
+        }
+        Term::Pattern(regex) => regex.is_match(&process.name) || regex.is_match(&process.command),
+        Term::User(user) => process.user == *user,
+        Term::State(states) => states.contains(process.state),
+        Term::Pids(pids) => pids.contains(&process.pid),
+        Term::Compare(field, operator, value) => {
+            let actual = match field {
+                Field::Cpu => process.cpu as f64,
+                Field::Memory => process.rss as f64,
+                Field::Virtual => process.virt as f64,
+                Field::Threads => process.threads as f64,
+                Field::Nice => process.nice as f64,
+                Field::Pid => process.pid as f64,
+                Field::Ppid => process.ppid as f64,
+            };
+            match operator {
+                Operator::Less => actual < *value,
+                Operator::LessOrEqual => actual <= *value,
+                Operator::Greater => actual > *value,
+                Operator::GreaterOrEqual => actual >= *value,
+                Operator::Equal => actual == *value,
+                Operator::NotEqual => actual != *value,
+            }
+        }
+        Term::Not(inner) => !term_matches(inner, process),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_kind_of_term() {
+        assert!(matches!(parse_term("Firefox"), Ok(Term::Text(text)) if text == "firefox"));
+        assert!(matches!(parse_term("re:^fire"), Ok(Term::Pattern(regex)) if regex.as_str() == "^fire"));
+        assert!(matches!(parse_term("/fox$/"), Ok(Term::Pattern(regex)) if regex.as_str() == "fox$"));
+        assert!(matches!(parse_term("user:root"), Ok(Term::User(user)) if user == "root"));
+        assert!(matches!(parse_term("u:www"), Ok(Term::User(user)) if user == "www"));
+        assert!(matches!(parse_term("state:rd"), Ok(Term::State(states)) if states == "RD"));
+        assert!(matches!(parse_term("pid:1,42,"), Ok(Term::Pids(pids)) if pids == vec![1, 42]));
+        assert!(matches!(parse_term("cpu>5"), Ok(Term::Compare(Field::Cpu, Operator::Greater, value)) if value == 5_f64));
+        assert!(matches!(parse_term("!user:root"), Ok(Term::Not(inner)) if matches!(*inner, Term::User(ref user) if user == "root")));
+        // A lone `!` or an unknown field is searched as text
+        assert!(matches!(parse_term("!"), Ok(Term::Text(text)) if text == "!"));
+        assert!(matches!(parse_term("foo=bar"), Ok(Term::Text(text)) if text == "foo=bar"));
+    }
+
+    #[test]
+    fn matches_the_two_character_operators_first() {
+        assert!(matches!(parse_term("mem>=1"), Ok(Term::Compare(Field::Memory, Operator::GreaterOrEqual, _))));
+        assert!(matches!(parse_term("virt<=1"), Ok(Term::Compare(Field::Virtual, Operator::LessOrEqual, _))));
+        assert!(matches!(parse_term("ppid!=1"), Ok(Term::Compare(Field::Ppid, Operator::NotEqual, _))));
+        assert!(matches!(parse_term("pid==1"), Ok(Term::Compare(Field::Pid, Operator::Equal, _))));
+        assert!(matches!(parse_term("thr<1"), Ok(Term::Compare(Field::Threads, Operator::Less, _))));
+        assert!(matches!(parse_term("ni=1"), Ok(Term::Compare(Field::Nice, Operator::Equal, value)) if value == 1_f64));
+    }
+
+    #[test]
+    fn parses_numbers_with_size_suffixes() {
+        assert_eq!(parse_number("12"), Some(12_f64));
+        assert_eq!(parse_number("-5"), Some(-5_f64));
+        assert_eq!(parse_number("2K"), Some(2048_f64));
+        assert_eq!(parse_number("1.5M"), Some(1.5 * 1024_f64 * 1024_f64));
+        assert_eq!(parse_number("1gb"), Some(1024_f64 * 1024_f64 * 1024_f64));
+        assert_eq!(parse_number("M"), None);
+        assert_eq!(parse_number("ten"), None);
+    }
+
+    #[test]
+    fn describes_the_invalid_terms() {
+        assert_eq!(ProcessFilter::parse("cpu>five").err().unwrap(), "Invalid number in 'cpu>five'");
+        assert_eq!(ProcessFilter::parse("pid:1,x").err().unwrap(), "Invalid process id list in 'pid:1,x'");
+        let error = ProcessFilter::parse("ok re:(").err().unwrap();
+        assert!(error.starts_with("Invalid pattern '(': "), "{}", error);
+        assert!(!error.contains('\n'));
+    }
+}
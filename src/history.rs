@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+/// The braille dots of a cell column from the bottom row to the top row, the
+/// left column first, see the Unicode braille patterns block
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x40, 0x04, 0x02, 0x01], [0x80, 0x20, 0x10, 0x08]];
+
+/// A bounded ring buffer of the recent samples of a metric, once full every
+/// new sample replaces the oldest one
+#[derive(Clone)]
+pub struct History {
+    capacity: usize,
+    samples: VecDeque<f32>,
+}
+
+impl History {
+    /// Creates an empty history
+    /// ### Parameters
+    /// * `capacity` - The count of samples kept
+    pub fn new(capacity: usize) -> History {
+        History {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a sample, dropping the oldest one when the history is full
+    /// ### Parameters
+    /// * `sample` - The sample to add
+    pub fn push(&mut self, sample: f32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Returns the newest sample
+    pub fn latest(&self) -> Option<f32> {
+        self.samples.back().cloned()
+    }
+
+    /// Returns the largest of the newest samples, 0 without samples
+    /// ### Parameters
+    /// * `count` - The count of the newest samples to look at
+    pub fn max(&self, count: usize) -> f32 {
+        self.samples.iter().rev().take(count).cloned().fold(0_f32, f32::max)
+    }
+
+    /// Returns the samples from the oldest to the newest
+    pub fn samples(&self) -> impl DoubleEndedIterator<Item = &f32> {
+        self.samples.iter()
+    }
+}
+
+/// Draws the newest samples as a filled area chart of braille characters, each
+/// character holds two samples side by side and four steps of height, the newest
+/// sample is at the right edge, returns the rows from the top to the bottom
+/// ### Parameters
+/// * `history` - The samples to draw
+/// * `width` - The width of the chart in characters
+/// * `height` - The height of the chart in characters
+/// * `max` - The value of a full column, larger samples are cut
+pub fn braille_graph(history: &History, width: usize, height: usize, max: f32) -> Vec<String> {
+    let dots = height * 4;
+    // The dot heights of the columns from the right edge to the left
+    let columns: Vec<usize> = history
+        .samples()
+        .rev()
+        .take(width * 2)
+        .map(|sample| {
+            if max <= 0_f32 || *sample <= 0_f32 {
+                return 0;
+            }
+            // Any sample above zero gets at least one dot so that it shows up
+            ((sample / max * dots as f32).round() as usize).max(1).min(dots)
+        })
+        .collect();
+    let mut rows: Vec<String> = Vec::with_capacity(height);
+    for row in 0..height {
+        // The dots below this row
+        let bottom = (height - 1 - row) * 4;
+        let mut line = String::with_capacity(width * 3);
+        for cell in (0..width).rev() {
+            let mut pattern: u32 = 0;
+            for (side, dot_column) in BRAILLE_DOTS.iter().enumerate() {
+                // The right side of the rightmost cell is the newest sample
+                let filled = columns.get(cell * 2 + 1 - side).cloned().unwrap_or(0);
+                for (step, dot) in dot_column.iter().enumerate() {
+                    if filled > bottom + step {
+                        pattern |= dot;
+                    }
+                }
+            }
+            line.push(if pattern == 0 { ' ' } else { std::char::from_u32(0x2800 + pattern).unwrap_or(' ') });
+        }
+        rows.push(line);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(samples: &[f32]) -> History {
+        let mut history = History::new(8);
+        for sample in samples {
+            history.push(*sample);
+        }
+        history
+    }
+
+    #[test]
+    fn drops_the_oldest_samples_when_full() {
+        let mut history = History::new(3);
+        assert_eq!(history.latest(), None);
+        for sample in 1..=5 {
+            history.push(sample as f32);
+        }
+        assert_eq!(history.samples().cloned().collect::<Vec<f32>>(), vec![3_f32, 4_f32, 5_f32]);
+        assert_eq!(history.latest(), Some(5_f32));
+        assert_eq!(history.max(3), 5_f32);
+        assert_eq!(history.max(0), 0_f32);
+    }
+
+    #[test]
+    fn empty_capacity_keeps_nothing() {
+        let mut history = History::new(0);
+        history.push(1_f32);
+        assert_eq!(history.samples().count(), 0);
+    }
+
+    #[test]
+    fn draws_full_and_empty_columns() {
+        // The newest two samples fill the rightmost cell, the older ones are empty
+        let rows = braille_graph(&history(&[0_f32, 0_f32, 100_f32, 100_f32]), 2, 1, 100_f32);
+        assert_eq!(rows, vec![String::from(" ⣿")]);
+        let rows = braille_graph(&history(&[100_f32, 0_f32]), 1, 2, 100_f32);
+        // The left dots of both rows
+        assert_eq!(rows, vec![String::from("⡇"), String::from("⡇")]);
+    }
+
+    #[test]
+    fn draws_partial_heights_from_the_bottom() {
+        // A quarter of two rows is the bottom two dots of the lower row
+        let rows = braille_graph(&history(&[25_f32, 50_f32]), 1, 2, 100_f32);
+        assert_eq!(rows[0], " ");
+        assert_eq!(rows[1], std::char::from_u32(0x2800 + 0x40 + 0x04 + 0x80 + 0x20 + 0x10 + 0x08).unwrap().to_string());
+        // Small values still show and large ones are cut to the height
+        let rows = braille_graph(&history(&[0.1_f32, 500_f32]), 1, 1, 100_f32);
+        assert_eq!(rows[0], std::char::from_u32(0x2800 + 0x40 + 0xb8).unwrap().to_string());
+    }
+
+    #[test]
+    fn nothing_is_drawn_without_samples_or_scale() {
+        assert_eq!(braille_graph(&History::new(4), 3, 1, 100_f32), vec![String::from("   ")]);
+        assert_eq!(braille_graph(&history(&[5_f32]), 1, 1, 0_f32), vec![String::from(" ")]);
+    }
+}
@@ -0,0 +1,315 @@
+use serde_json::{json, Map, Value};
+use systemstat::IpAddr;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::datafetcher::{CPUData, DiskData, DiskIOData, NetworkData, ProcessInfo, RAMData, SystemData};
+
+/// The version of the JSON schema, raised whenever a field is renamed, removed or
+/// changes its meaning, new fields can be added without raising it
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Returns the snapshot of the system as a JSON object, the sizes are in bytes, the
+/// rates in bytes per second and the usages in percent
+/// ### Parameters
+/// * `sys` - The system data to convert
+/// * `processes` - The processes to include, already filtered and sorted
+pub fn snapshot(sys: &SystemData, processes: &[&ProcessInfo]) -> Value {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs_f64()).unwrap_or(0_f64);
+    json!({
+        "schema": SCHEMA_VERSION,
+        "version": env!("CARGO_PKG_VERSION"),
+        "timestamp": timestamp,
+        "uptime": sys.uptime.as_secs(),
+        "cpu": cpu(&sys.cpu),
+        "memory": memory(&sys.ram),
+        "disks": disks(&sys.disk),
+        "block_devices": block_devices(&sys.diskio),
+        "network": network(&sys.network),
+        "sensors": sys.sensors.list.iter().map(|sensor| json!({
+            "chip": sensor.chip,
+            "device": sensor.device,
+            "label": sensor.label,
+            "kind": format!("{:?}", sensor.kind).to_lowercase(),
+            "value": sensor.value,
+            "unit": sensor.kind.unit(),
+            "min": sensor.min,
+            "max": sensor.max,
+            "critical": sensor.crit,
+        })).collect::<Vec<Value>>(),
+        "power_supplies": sys.power.list.iter().map(|supply| json!({
+            "name": supply.name,
+            "kind": supply.kind,
+            "online": supply.online,
+            "status": supply.status,
+            "capacity": supply.capacity,
+            "power": supply.power,
+            "time_to_empty": supply.time_to_empty.map(|time| time.as_secs()),
+            "time_to_full": supply.time_to_full.map(|time| time.as_secs()),
+        })).collect::<Vec<Value>>(),
+        "processes": processes.iter().map(|process| json!({
+            "pid": process.pid,
+            "ppid": process.ppid,
+            "name": process.name,
+            "user": process.user,
+            "state": process.state.to_string(),
+            "nice": process.nice,
+            "cpu": process.cpu,
+            "rss": process.rss,
+            "virtual": process.virt,
+            "threads": process.threads,
+            "start_time": process.start_time,
+            "command": process.command,
+        })).collect::<Vec<Value>>(),
+    })
+}
+
+/// Converts the CPU data, the core times are percentages of the time since the previous fetch
+/// ### Parameters
+/// * `cpu` - The CPU data to convert
+fn cpu(cpu: &CPUData) -> Value {
+    let load_average = cpu.load_average.last().map(|load| json!({ "one": load.one, "five": load.five, "fifteen": load.fifteen }));
+    let cores: Vec<Value> = cpu
+        .load
+        .iter()
+        .zip(cpu.usage.iter())
+        .map(|(load, usage)| {
+            json!({
+                "usage": usage,
+                "user": load.user * 100_f32,
+                "nice": load.nice * 100_f32,
+                "system": load.system * 100_f32,
+                "irq": load.interrupt * 100_f32,
+                "softirq": load.softirq * 100_f32,
+                "iowait": load.iowait * 100_f32,
+                "steal": load.steal * 100_f32,
+                "guest": (load.guest + load.guest_nice) * 100_f32,
+                "idle": load.idle * 100_f32,
+            })
+        })
+        .collect();
+    json!({
+        "usage": cpu.total,
+        "cores": cores,
+        "load_average": load_average,
+        "tasks_running": cpu.tasks_running,
+        "tasks_total": cpu.tasks_total,
+        "temperatures": cpu.temp,
+    })
+}
+
+/// Converts the memory data
+/// ### Parameters
+/// * `ram` - The memory data to convert
+fn memory(ram: &RAMData) -> Value {
+    json!({
+        "total": ram.total,
+        "used": ram.used,
+        "free": ram.free,
+        "available": ram.available,
+        "usage": ram.percentage,
+        "buffers": ram.buffers,
+        "cached": ram.cached,
+        "shared": ram.shared,
+        "slab_reclaimable": ram.slab_reclaimable,
+        "slab_unreclaimable": ram.slab_unreclaimable,
+        "dirty": ram.dirty,
+        "writeback": ram.writeback,
+        "swap": {
+            "total": ram.swap_total,
+            "free": ram.swap_free,
+            "used": ram.swap_total.saturating_sub(ram.swap_free),
+            "cached": ram.swap_cached,
+        },
+        "huge_pages": {
+            "total": ram.hugepages_total,
+            "free": ram.hugepages_free,
+            "size": ram.hugepage_size,
+        },
+    })
+}
+
+/// Converts the mounted filesystems
+/// ### Parameters
+/// * `disk` - The disk data to convert
+fn disks(disk: &DiskData) -> Vec<Value> {
+    (0..disk.count)
+        .map(|i| {
+            json!({
+                "device": disk.fs_mounted_from[i],
+                "mountpoint": disk.fs_mounted_on[i],
+                "type": disk.fs_type[i],
+                "total": disk.total[i],
+                "used": disk.used[i],
+                "free": disk.free[i],
+                "usage": disk.percentage[i],
+                "inodes_total": disk.inodes_total[i],
+                "inodes_free": disk.inodes_free[i],
+                "pseudo": disk.pseudo[i],
+            })
+        })
+        .collect()
+}
+
+/// Converts the block device I/O activity
+/// ### Parameters
+/// * `diskio` - The block device data to convert
+fn block_devices(diskio: &DiskIOData) -> Vec<Value> {
+    (0..diskio.count)
+        .map(|i| {
+            json!({
+                "name": diskio.names[i],
+                "read_rate": diskio.read_rate[i],
+                "write_rate": diskio.write_rate[i],
+                "read_iops": diskio.read_iops[i],
+                "write_iops": diskio.write_iops[i],
+                "latency_ms": diskio.latency[i],
+                "queue_depth": diskio.queue_depth[i],
+                "in_flight": diskio.in_flight[i],
+                "utilisation": diskio.utilisation[i],
+            })
+        })
+        .collect()
+}
+
+/// Converts the network interfaces, the addresses are strings without the unsupported ones
+/// ### Parameters
+/// * `network` - The network data to convert
+fn network(network: &NetworkData) -> Vec<Value> {
+    (0..network.count)
+        .map(|i| {
+            let addresses: Vec<String> = network
+                .adresses
+                .get(i)
+                .map(|addresses| addresses.iter().filter_map(|address| ip_address(&address.addr)).collect())
+                .unwrap_or_default();
+            let mut interface = Map::new();
+            interface.insert(String::from("name"), json!(network.names[i]));
+            interface.insert(String::from("addresses"), json!(addresses));
+            for (name, values) in [
+                ("rx_bytes", &network.rx),
+                ("tx_bytes", &network.tx),
+                ("rx_packets", &network.rx_packets),
+                ("tx_packets", &network.tx_packets),
+                ("rx_errors", &network.rx_errors),
+                ("tx_errors", &network.tx_errors),
+                ("rx_drops", &network.rx_drops),
+                ("tx_drops", &network.tx_drops),
+                ("rx_rate", &network.rx_rate),
+                ("tx_rate", &network.tx_rate),
+            ]
+            .iter()
+            {
+                interface.insert(name.to_string(), json!(values.get(i).cloned().unwrap_or(0)));
+            }
+            Value::Object(interface)
+        })
+        .collect()
+}
+
+/// Formats the address of an interface, none for the empty and unsupported ones
+/// ### Parameters
+/// * `address` - The address to format
+fn ip_address(address: &IpAddr) -> Option<String> {
+    match address {
+        IpAddr::V4(address) => Some(address.to_string()),
+        IpAddr::V6(address) => Some(address.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datafetcher;
+    use std::net::Ipv4Addr;
+    use systemstat::NetworkAddrs;
+
+    /// Returns the sorted keys of a JSON object
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|key| key.as_str()).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn pins_the_schema() {
+        // Renaming or removing any of these raises the schema version, and this test with it
+        assert_eq!(SCHEMA_VERSION, 1);
+        let (sys, _) = datafetcher::start_data_fetcher().unwrap();
+        let process = ProcessInfo::fixture(42, 1, "root", 1.5, 4096);
+        let value = snapshot(&sys, &[&process]);
+        assert_eq!(value["schema"], SCHEMA_VERSION);
+        assert_eq!(
+            keys(&value),
+            vec![
+                "block_devices",
+                "cpu",
+                "disks",
+                "memory",
+                "network",
+                "power_supplies",
+                "processes",
+                "schema",
+                "sensors",
+                "timestamp",
+                "uptime",
+                "version",
+            ]
+        );
+        assert_eq!(keys(&value["cpu"]), vec!["cores", "load_average", "tasks_running", "tasks_total", "temperatures", "usage"]);
+        assert_eq!(
+            keys(&value["memory"]),
+            vec![
+                "available",
+                "buffers",
+                "cached",
+                "dirty",
+                "free",
+                "huge_pages",
+                "shared",
+                "slab_reclaimable",
+                "slab_unreclaimable",
+                "swap",
+                "total",
+                "usage",
+                "used",
+                "writeback",
+            ]
+        );
+        assert_eq!(
+            keys(&value["processes"][0]),
+            vec!["command", "cpu", "name", "nice", "pid", "ppid", "rss", "start_time", "state", "threads", "user", "virtual"]
+        );
+    }
+
+    #[test]
+    fn converts_the_parallel_lists_into_objects() {
+        let network = NetworkData {
+            count: 1,
+            names: vec![String::from("eth0")],
+            adresses: vec![vec![
+                NetworkAddrs { addr: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), netmask: IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0)) },
+                NetworkAddrs { addr: IpAddr::Unsupported, netmask: IpAddr::Empty },
+            ]],
+            rx: vec![1024],
+            tx: vec![2048],
+            rx_packets: vec![8],
+            tx_packets: vec![16],
+            rx_errors: vec![0],
+            tx_errors: vec![1],
+            rx_drops: vec![2],
+            tx_drops: vec![0],
+            rx_rate: vec![100],
+            tx_rate: vec![],
+        };
+        let interfaces = super::network(&network);
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0]["name"], "eth0");
+        assert_eq!(interfaces[0]["addresses"], json!(["192.0.2.1"]));
+        assert_eq!(interfaces[0]["rx_bytes"], 1024);
+        assert_eq!(interfaces[0]["tx_errors"], 1);
+        // Missing counters are zero rather than missing fields
+        assert_eq!(interfaces[0]["tx_rate"], 0);
+    }
+}
@@ -1,38 +1,81 @@
 extern crate systemstat;
 
-use std::io::stdout;
+use std::env;
 use std::error::Error;
 use std::process;
 use std::thread;
-use std::time::Duration;
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 
 use ctrlc;
 
 use futures::executor::block_on;
-use systemstat::{saturating_sub_bytes, Platform, System};
 
-use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
-use crossterm::terminal::{
-    Clear,
-    ClearType::{All, CurrentLine},
-};
-use crossterm::{
-    cursor::{MoveTo},
-    execute,
-    event::{Event, read, KeyCode},
-};
+use crossterm::event::{Event, read, KeyCode, KeyEvent, KeyModifiers};
 
+use args::{Action, Options};
 use datafetcher::SystemData;
-
-const REFRESH: Duration = Duration::from_secs(1);
+use filter::ProcessFilter;
+use push::Pusher;
+use record::Recorder;
+use ui::{Jump, Popup, Prompt, PromptKind, SortColumn, UIState};
 
 mod ui;
+mod args;
+mod batch;
+mod color;
+mod control;
+mod cpuinfo;
 mod datafetcher;
+mod exporter;
+mod filter;
+mod history;
+mod json;
+mod metrics;
+mod power;
+mod procfs;
+mod proctree;
+mod push;
+mod record;
+mod sensors;
+mod sysfs;
 mod utils;
 
 /// The main function of the program
 fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let options = match args::parse(&arguments, args::default_config()) {
+        Ok(Action::Run(options)) => options,
+        Ok(Action::Help) => {
+            print!("{}", args::help());
+            process::exit(0);
+        },
+        Ok(Action::Version) => {
+            println!("{}", args::version());
+            process::exit(0);
+        },
+        Err(e) => {
+            eprintln!("rctop: {}", e);
+            process::exit(2);
+        }
+    };
+    if let Some(address) = &options.listen {
+        // The exporter runs headless until it is stopped
+        if let Err(e) = exporter::run(address, &options) {
+            eprintln!("rctop: {}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+    if options.batch {
+        // The batch mode never touches the terminal so it works when stdout is a pipe or a file
+        if let Err(e) = batch::run(&options) {
+            eprintln!("rctop: {}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+    color::set_enabled(options.color.enabled());
 
     ui::init();
     // CTRL-C handler
@@ -43,7 +86,7 @@ fn main() {
     .expect("Error setting Ctrl + C handler");
 
     // Block main thread until process finishes
-    match block_on(async_main()) {
+    match block_on(async_main(options)) {
         Ok(_) => {
             ui::exit();
             process::exit(0);
@@ -56,244 +99,497 @@ fn main() {
     };
 }
 
-async fn async_main() -> Result<String, Box<dyn Error>> {
+async fn async_main(options: Options) -> Result<String, Box<dyn Error>> {
     let mut term_size = crossterm::terminal::size()?;
-    let system_data: SystemData = datafetcher::start_data_fetcher()?;
+    let (system_data, fetch_state): (SystemData, _) = datafetcher::start_data_fetcher()?;
     let system_data_arc = Arc::new(Mutex::new(system_data));
     let thr_data = system_data_arc.clone();
-    let thr_data_2 = system_data_arc.clone();
-    datafetcher::start_fetch(thr_data, REFRESH)?;
-
-    // thread::spawn(move ||  {
-    //     loop {
-    //         let mut data = thr_data.lock().unwrap();
-    //         *data += "Bababooey ";
-    //         drop(data);
-    //         thread::sleep(Duration::from_millis(100));
-    //     }
-    // });
+    // The id of the process in the details tab, kept in sync with the UI state by the render loop
+    let detail_pid: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
+    let recorder = match &options.record {
+        Some(path) => Some(Recorder::open(path.clone())?),
+        None => None,
+    };
+    let pusher = options.push.clone().map(Pusher::start);
+    datafetcher::start_fetch(thr_data, fetch_state, options.interval, detail_pid.clone(), recorder, pusher)?;
+    let mut initial_state = UIState::new();
+    initial_state.selection.0 = options.tab;
+    if let Some(column) = options.sort {
+        initial_state.sort_by(column);
+    }
+    if let Some(expression) = options.filter {
+        // The expression was checked when the arguments were parsed
+        let filter = ProcessFilter::parse(&expression)?;
+        initial_state.filter = Some((expression, filter));
+    }
+    let ui_state = Arc::new(Mutex::new(initial_state));
+    let thr_ui_state = ui_state.clone();
+    // The keyboard thread asks for an immediate redraw after handling a key
+    let (redraw_tx, redraw_rx) = channel::<()>();
 
     // Create thread for keyboard events
     thread::spawn(move || -> crossterm::Result<()> {
-        let thr_data2 = system_data_arc.clone();
-        let term_size = crossterm::terminal::size()?;
-        let mut selection: (usize, usize) = (0, 0);
         // Loop for keyboard events
         loop {
             // `read()` blocks until an `Event` is available
-            match read()? {
+            let event = read()?;
+            let mut state = thr_ui_state.lock().unwrap();
+            let term_size = crossterm::terminal::size()?;
+            let page = (term_size.1 as usize).saturating_sub(4).max(1);
+            match event {
+                // Open popups take all key presses
+                Event::Key(event) if state.popup.is_some() => {
+                    state.message = None;
+                    handle_popup_key(&mut state, event);
+                },
+                Event::Key(event) if state.prompt.is_some() => {
+                    state.message = None;
+                    handle_prompt_key(&mut state, event);
+                },
                 Event::Key(event) => {
-                    println!("{:?}", event);
-                    
+                    state.message = None;
                     match event.code {
                         // Close the program gracefully
                         KeyCode::Char('q') => {
                             ui::exit();
                             process::exit(0);
                         },
+                        // Raw mode doesn't turn Ctrl + C into a signal
+                        KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                            ui::exit();
+                            process::exit(0);
+                        },
                         KeyCode::Char('c') => {
-                            ui::reset();
-                            ui::update_menu_header(&mut selection, term_size);
-                            ui::print_system_data(&thr_data2, &mut selection, term_size);
-                        },
-                        KeyCode::Up => {
-                            if selection.1 != 0 {
-                                if selection.1 != 1 {
-                                    ui::update_menu_header(&mut selection, term_size);
-                                }
-                                ui::print_system_data(&thr_data2, &mut selection, term_size);
-                            }
+                            ui::reset()?;
+                        },
+                        KeyCode::Char('a') => {
+                            state.show_pseudo_fs = !state.show_pseudo_fs;
+                        },
+                        KeyCode::Up if state.selection.1 != 0 => {
+                            state.selection.1 -= 1;
                         },
                         KeyCode::Down => {
-                            if selection.1 == 0 {
-                                ui::update_menu_header(&mut selection, term_size);
-                            }
-                            ui::print_system_data(&thr_data2, &mut selection, term_size);
+                            state.selection.1 += 1;
+                        },
+                        KeyCode::PageUp => {
+                            state.selection.1 = state.selection.1.saturating_sub(page).max(1);
+                        },
+                        KeyCode::PageDown => {
+                            state.selection.1 += page;
+                        },
+                        KeyCode::Home => {
+                            state.selection.1 = 1;
+                        },
+                        KeyCode::End => {
+                            // Clamped to the last row when drawn
+                            state.selection.1 = usize::MAX;
                         },
                         KeyCode::Left => {
-                            if selection.1 == 0 {
-                                ui::update_menu_header(&mut selection, term_size);
-                                ui::print_system_data(&thr_data2, &mut selection, term_size);
+                            if state.selection.1 == 0 {
+                                state.selection.0 = (state.selection.0 + ui::TABS.len() - 1) % ui::TABS.len();
+                            } else if state.tree_view {
+                                set_collapsed(&mut state, true);
                             }
                         },
                         KeyCode::Right => {
-                            if selection.1 == 0 {
-                                ui::update_menu_header(&mut selection, term_size);
-                                ui::print_system_data(&thr_data2, &mut selection, term_size);
+                            if state.selection.1 == 0 {
+                                state.selection.0 = (state.selection.0 + 1) % ui::TABS.len();
+                            } else if state.tree_view {
+                                set_collapsed(&mut state, false);
+                            }
+                        },
+                        // Process tree view and collapsing of its subtrees
+                        KeyCode::Char('t') | KeyCode::F(5) => {
+                            state.tree_view = !state.tree_view;
+                        },
+                        KeyCode::Char('-') => set_collapsed(&mut state, true),
+                        KeyCode::Char('+') => set_collapsed(&mut state, false),
+                        KeyCode::Char(' ') => {
+                            if let Some((pid, _)) = state.selected_process {
+                                let collapse = !state.collapsed.contains(&pid);
+                                set_collapsed(&mut state, collapse);
+                            }
+                        },
+                        KeyCode::Tab => {
+                            state.selection = ((state.selection.0 + 1) % ui::TABS.len(), 0);
+                        },
+                        KeyCode::BackTab => {
+                            state.selection = ((state.selection.0 + ui::TABS.len() - 1) % ui::TABS.len(), 0);
+                        },
+                        // Process table sorting, htop style shortcuts and cycling through the columns
+                        KeyCode::Char('P') => state.sort_by(SortColumn::Cpu),
+                        KeyCode::Char('M') => state.sort_by(SortColumn::Memory),
+                        KeyCode::Char('N') => state.sort_by(SortColumn::Pid),
+                        KeyCode::Char('T') => state.sort_by(SortColumn::Start),
+                        KeyCode::Char('<') => {
+                            let column = state.sort.previous();
+                            state.sort_by(column);
+                        },
+                        KeyCode::Char('>') => {
+                            let column = state.sort.next();
+                            state.sort_by(column);
+                        },
+                        KeyCode::Char('I') => {
+                            state.sort_descending = !state.sort_descending;
+                        },
+                        // Search and filter prompts, the search can be cycled through afterwards
+                        KeyCode::Char('/') | KeyCode::F(3) if state.search.is_none() => {
+                            state.selection.0 = ui::TAB_PROCESSES;
+                            state.prompt = Some(Prompt { kind: PromptKind::Search, input: String::new(), error: None });
+                        },
+                        KeyCode::Char('/') => {
+                            state.selection.0 = ui::TAB_PROCESSES;
+                            let input = state.search.as_ref().map(|(expression, _)| expression.clone()).unwrap_or_default();
+                            state.prompt = Some(Prompt { kind: PromptKind::Search, input, error: None });
+                        },
+                        KeyCode::Char('\\') | KeyCode::F(4) => {
+                            state.selection.0 = ui::TAB_PROCESSES;
+                            let input = state.filter.as_ref().map(|(expression, _)| expression.clone()).unwrap_or_default();
+                            state.prompt = Some(Prompt { kind: PromptKind::Filter, input, error: None });
+                        },
+                        KeyCode::F(3) if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                            state.jump = Some(Jump::Previous);
+                        },
+                        KeyCode::Char('n') | KeyCode::F(3) => {
+                            state.jump = Some(Jump::Next);
+                        },
+                        KeyCode::Char('b') => {
+                            state.jump = Some(Jump::Previous);
+                        },
+                        KeyCode::Esc if state.selection.0 == ui::TAB_DETAILS || state.selection.0 == ui::TAB_THREADS => {
+                            state.selection = (ui::TAB_PROCESSES, 0);
+                        },
+                        KeyCode::Esc => {
+                            state.search = None;
+                        },
+                        // Process details, of the selected process or of any PID
+                        KeyCode::Enter if state.selection.0 == ui::TAB_PROCESSES => {
+                            if let Some((pid, _)) = state.selected_process {
+                                state.detail = Some(pid);
+                                state.selection = (ui::TAB_DETAILS, 1);
+                            }
+                        },
+                        KeyCode::Char('H') => {
+                            if state.selection.0 == ui::TAB_PROCESSES {
+                                if let Some((pid, _)) = state.selected_process {
+                                    state.detail = Some(pid);
+                                }
+                            }
+                            if state.detail.is_some() {
+                                state.selection = (ui::TAB_THREADS, 1);
+                            } else {
+                                state.message = Some(String::from("Select a process first"));
+                            }
+                        },
+                        KeyCode::Char('d') => {
+                            state.prompt = Some(Prompt { kind: PromptKind::Pid, input: String::new(), error: None });
+                        },
+                        KeyCode::Char('r') | KeyCode::F(7) if state.selection.0 != ui::TAB_OVERVIEW => {
+                            if let Some((pid, name)) = state.selected_process.clone() {
+                                state.popup = Some(priority_popup(pid, name));
+                            } else {
+                                state.message = Some(String::from("Select a process first"));
+                            }
+                        },
+                        KeyCode::Char('k') | KeyCode::F(9) if state.selection.0 != ui::TAB_OVERVIEW => {
+                            if let Some((pid, name)) = state.selected_process.clone() {
+                                state.popup = Some(Popup::Signal {
+                                    pid,
+                                    name,
+                                    selected: 0,
+                                    number: String::new(),
+                                    confirm: None,
+                                    error: None,
+                                });
+                            } else {
+                                state.message = Some(String::from("Select a process first"));
                             }
                         },
                         _ => {
                         }
                     }
                 },
-                Event::Mouse(event) => println!("{:?}", event),
+                Event::Mouse(_event) => {},
                 Event::Resize(_width, _height) => {
-                    ui::reset();
+                    ui::reset()?;
                 },
             }
+            drop(state);
+            if redraw_tx.send(()).is_err() {
+                return Ok(());
+            }
         }
-        Ok(())
     });
 
     for _i in 0..term_size.1 {
         print!("\n");
     }
+    ui::reset()?;
+    let mut refreshes: u64 = 0;
     loop {
-        thread::sleep(REFRESH);
-        let sys = thr_data_2.lock().unwrap();
+        // Wait for the refresh interval or until the keyboard thread asks for a redraw
+        match redraw_rx.recv_timeout(options.interval) {
+            Ok(()) => {},
+            Err(RecvTimeoutError::Timeout) => refreshes += 1,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(options.interval);
+                refreshes += 1;
+            },
+        }
+        let sys = system_data_arc.lock().unwrap();
+        let mut state = ui_state.lock().unwrap();
+        *detail_pid.lock().unwrap() = state.detail;
         term_size = crossterm::terminal::size()?;
-        print!(" ");
-
-        execute!(stdout(), ResetColor, MoveTo(0, 2))?;
-
-        // Total CPU usage is 0 at first in case of error
-        let mut total_cpu: f32 = 0_f32;
-        // Fetches the CPU usage for each core and prints it
-        let cpu_usages = &sys.cpu;
-        let cpu_count_string_length: usize = cpu_usages.count.to_string().len();
-        for i in 0..cpu_usages.count {
-            execute!(stdout(), Clear(CurrentLine))?;
-            print!("CPU {}:", i);
-            for _j in i.to_string().len()..cpu_count_string_length + 1 {
-                print!(" ");
-            }
-            print_bar(
-                term_size.0 - 8,
-                100_f32 - &cpu_usages.load[i].idle,
-                Color::DarkGreen,
-            )?;
-            println!("");
-            execute!(stdout(), Clear(CurrentLine))?;
-            //println!("Load: {:.2}%", 100_f32 - &cpu_usages[i][4]);
-            // Sum up the cpu usages
-            total_cpu += 100_f32 - &cpu_usages.load[i].idle;
+        ui::update_top_header(&sys, term_size)?;
+        ui::update_menu_header(&state.selection, term_size)?;
+        ui::print_system_data(&sys, &mut state, term_size)?;
+        ui::update_bottom_bar(&sys, &state.message, term_size)?;
+        if let Some(prompt) = &state.prompt {
+            ui::print_prompt(prompt, term_size)?;
         }
-        // Get total cpu usage by dividing with the core count
-        total_cpu = 100_f32 - total_cpu / cpu_usages.count as f32;
-
-
-        println!(" ");
-        execute!(stdout(), Clear(CurrentLine))?;
-        print!("Memory: ");
-        print_bar(
-            term_size.0 - 8,
-            sys.ram.used as f32 / sys.ram.total as f32 * 100_f32,
-            Color::DarkYellow,
-        )?;
-        println!("");
-        // execute!(stdout(), Clear(CurrentLine))?;
-        // print!("Swap: ");
-        // print_bar(
-        //     term_size.0 - 5,
-        //     memory[3] as f32 / memory[2] as f32 * 100_f32,
-        //     Color::DarkYellow,
-        // );
-        // println!("");
-
-
-        //print_graph_stats(&cpu_vec, term_size.0 / 2, term_size.1 - 3, term_size.0, term_size.1);
-        execute!(
-            stdout(),
-            MoveTo(0, term_size.1),
-            Clear(CurrentLine),
-            SetBackgroundColor(Color::DarkCyan)
-        )
-        ?;
-        for _i in 0..term_size.0 {
-            print!(" ");
+        if options.iterations.is_some_and(|iterations| refreshes >= iterations) {
+            return Ok(String::new());
         }
-        let mut bottom_left_str: String = String::new();
-        let mut bottom_right_str: String = String::new();
-        bottom_left_str += &format!("CPU: {:.2}% ", total_cpu);
-        bottom_left_str += &format!("RAM: {} / {} ", utils::parse_size(&sys.ram.used), utils::parse_size(&sys.ram.total));
-        // let battery = sys.battery_life()?;
-        // bottom_right_str += &format!(
-        //     "Battery: {:.2}%, {}",
-        //     battery.remaining_capacity * 100.0,
-        //     utils::parse_time(&battery.remaining_time)
-        // );
-        execute!(
-            stdout(),
-            MoveTo(0, term_size.1),
-            Clear(CurrentLine),
-            SetBackgroundColor(Color::DarkCyan)
-        )?;
-        print!(" ");
-        if term_size.0 > bottom_left_str.len() as u16 + bottom_right_str.len() as u16 + 2 {
-            print!("{}", bottom_left_str);
-            for _i in 0..(term_size.0 as usize - bottom_left_str.len() - bottom_right_str.len() - 2) {
-                print!(" ");
+    }
+}
+
+/// Handles a key press while a text prompt is open, searches are applied while typing
+/// and filters once they are submitted
+/// ### Parameters
+/// * `state` - The state of the UI with the open prompt
+/// * `event` - The key press
+fn handle_prompt_key(state: &mut UIState, event: KeyEvent) {
+    let prompt = match &mut state.prompt {
+        Some(prompt) => prompt,
+        None => return,
+    };
+    match event.code {
+        KeyCode::Esc => {
+            // Cancelling a search also removes its highlights, a filter stays as it was
+            if prompt.kind == PromptKind::Search {
+                state.search = None;
             }
-            print!("{} ", bottom_right_str);
-        }
-        else if term_size.0 > bottom_left_str.len() as u16 + 1 {
-            print!("{}", bottom_left_str);
-            for _i in 0..(term_size.0 as usize - bottom_left_str.len() - 1) {
-                print!(" ");
+            state.prompt = None;
+            return;
+        },
+        KeyCode::Enter => {
+            if prompt.kind == PromptKind::Search {
+                if prompt.error.is_none() {
+                    state.prompt = None;
+                }
+                return;
             }
-        } else {
-            bottom_left_str.truncate(term_size.0 as usize - 5);
-            bottom_left_str += "...";
-            print!("{} ", bottom_left_str);
+            if prompt.kind == PromptKind::Pid {
+                match prompt.input.trim().parse::<u32>() {
+                    Ok(pid) => {
+                        state.detail = Some(pid);
+                        state.selection = (ui::TAB_DETAILS, 1);
+                        state.prompt = None;
+                    },
+                    Err(_) => prompt.error = Some(String::from("Not a process id")),
+                }
+                return;
+            }
+            if prompt.input.trim().is_empty() {
+                state.filter = None;
+                state.prompt = None;
+                return;
+            }
+            match ProcessFilter::parse(&prompt.input) {
+                Ok(filter) => {
+                    state.filter = Some((prompt.input.clone(), filter));
+                    state.selection.1 = state.selection.1.min(1);
+                    state.prompt = None;
+                },
+                Err(e) => prompt.error = Some(e),
+            }
+            return;
+        },
+        KeyCode::Backspace => {
+            prompt.input.pop();
+        },
+        KeyCode::Char(character) => {
+            prompt.input.push(character);
+        },
+        _ => return,
+    }
+    prompt.error = None;
+    if prompt.kind == PromptKind::Search {
+        if prompt.input.trim().is_empty() {
+            state.search = None;
+            return;
+        }
+        match ProcessFilter::parse(&prompt.input) {
+            Ok(search) => {
+                state.search = Some((prompt.input.clone(), search));
+                state.jump = Some(Jump::First);
+            },
+            Err(e) => prompt.error = Some(e),
         }
-        execute!(stdout(), ResetColor)?;
     }
 }
 
-// fn print_graph_stats(
-//     cpu_vec: &std::vec::Vec<f32>,
-//     max_width: u16,
-//     max_height: u16,
-//     x_offset: u16,
-//     y_offset: u16,
-// ) {
-//     let mut index: usize = 0;
-//     let length = cpu_vec.len();
-//     for i in y_offset - max_height..y_offset {
-//         execute!(stdout(), MoveTo(0, i))?;
-//         execute!(stdout(), Clear(CurrentLine))?;
-//     }
-//     while index < max_width.into() && index < length {
-//         let height = max_height as f32 / 100_f32 * cpu_vec[&length - 1 - &index];
-//         let floored: u16 = height as u16;
-//         execute!(
-//             stdout(),
-//             MoveTo(x_offset - index as u16, y_offset - max_height + floored)
-//         )
-//         ?;
-//         if (height - floored as f32) <= 0.33 {
-//             print!("_");
-//         } else if (height - floored as f32) <= 0.66 {
-//             print!("-");
-//         } else {
-//             print!("¯");
-//         }
-//         index += 1;
-//     }
-// }
-
-/// Prints a bar that is as long as the percentage of the given terminal width
+/// Collapses or expands the subtree of the selected process in the process tree
 /// ### Parameters
-/// * `max_width` - The max width of the bar
-/// * `percentage` - The percentage of the max width the bar is going to be
-fn print_bar(max_width: u16, percentage: f32, color: Color) -> Result<(), Box<dyn Error>> {
-    execute!(stdout(), SetForegroundColor(color))?;
-    let block_count = max_width as f32 / 100_f32 * percentage;
-    let mut index: u16 = 0;
-    let floored = block_count as u16;
-    // Print the full bars
-    while index < floored {
-        print!("⧛");
-        index = index + 1;
+/// * `state` - The state of the UI
+/// * `collapse` - Whether the subtree is collapsed or expanded
+fn set_collapsed(state: &mut UIState, collapse: bool) {
+    if !state.tree_view || state.selection.0 != ui::TAB_PROCESSES {
+        return;
     }
-    // Determine the last bar from decimal
-    if floored != 100 {
-        if (block_count - floored as f32) <= 0.5 {
-            print!("⧙");
+    if let Some((pid, _)) = state.selected_process {
+        if collapse {
+            state.collapsed.insert(pid);
         } else {
-            print!(" ");
+            state.collapsed.remove(&pid);
         }
     }
-    execute!(stdout(), ResetColor)?;
-    Ok(())
+}
+
+/// Handles a key press while a popup is open
+/// ### Parameters
+/// * `state` - The state of the UI with the open popup
+/// * `event` - The key press
+fn handle_popup_key(state: &mut UIState, event: KeyEvent) {
+    let mut close = event.code == KeyCode::Esc;
+    match &mut state.popup {
+        Some(Popup::Signal { pid, name, selected, number, confirm, error }) => {
+            match confirm {
+                // Waiting for the confirmation of the chosen signal
+                Some(signal) => match event.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                        let signal = *signal;
+                        match control::send_signal(*pid, signal) {
+                            Ok(()) => {
+                                state.message = Some(format!("Sent {} to {} ({})", control::signal_name(signal), name, pid));
+                                close = true;
+                            },
+                            // Permission errors and exited processes are shown in the popup
+                            Err(e) => {
+                                *error = Some(format!("Failed to send {}: {}", control::signal_name(signal), e));
+                                *confirm = None;
+                            },
+                        }
+                    },
+                    KeyCode::Char('n') | KeyCode::Char('N') => {
+                        *confirm = None;
+                    },
+                    _ => {},
+                },
+                None => match event.code {
+                    KeyCode::Up => {
+                        number.clear();
+                        *selected = selected.saturating_sub(1);
+                    },
+                    KeyCode::Down => {
+                        number.clear();
+                        *selected = (*selected + 1).min(control::SIGNALS.len().saturating_sub(1));
+                    },
+                    KeyCode::Char(digit) if digit.is_ascii_digit() && number.len() < 3 => {
+                        number.push(digit);
+                    },
+                    KeyCode::Backspace => {
+                        number.pop();
+                    },
+                    KeyCode::Enter => {
+                        *error = None;
+                        if !number.is_empty() {
+                            *confirm = number.parse().ok();
+                        } else if let Some((_, signal)) = control::SIGNALS.get(*selected) {
+                            *confirm = Some(*signal);
+                        }
+                    },
+                    _ => {},
+                },
+            }
+        },
+        Some(Popup::Priority { pid, name, field, nice, io_class, io_level, subtree, current: _, initial, error }) => {
+            match event.code {
+                KeyCode::Up => *field = field.saturating_sub(1),
+                KeyCode::Down => *field = (*field + 1).min(3),
+                KeyCode::Left | KeyCode::Right => {
+                    let step: i32 = if event.code == KeyCode::Left { -1 } else { 1 };
+                    match field {
+                        0 => *nice = (*nice + step).clamp(control::NICE_RANGE.0, control::NICE_RANGE.1),
+                        1 => *io_class = (*io_class as i32 + step).rem_euclid(control::IO_CLASSES.len() as i32) as u32,
+                        2 => *io_level = (*io_level as i32 + step).clamp(0, 7) as u32,
+                        _ => *subtree = !*subtree,
+                    }
+                },
+                KeyCode::Enter => {
+                    // Only the changed values are written, the others may not have been readable
+                    let has_levels = *io_class == 1 || *io_class == 2;
+                    let nice_changed = *nice != initial.0;
+                    let io_changed = *io_class != initial.1 || (has_levels && *io_level != initial.2);
+                    if !nice_changed && !io_changed {
+                        state.message = Some(format!("The priority of {} ({}) is unchanged", name, pid));
+                        close = true;
+                    } else {
+                        let pids = if *subtree { control::subtree_pids(*pid) } else { vec![*pid] };
+                        let mut failures: Vec<String> = Vec::new();
+                        for target in &pids {
+                            if nice_changed {
+                                if let Err(e) = control::set_nice(*target, *nice) {
+                                    failures.push(format!("nice of {}: {}", target, e));
+                                }
+                            }
+                            if io_changed {
+                                // The level of the classes without levels has to be 0
+                                let level = if has_levels { *io_level } else { 0 };
+                                if let Err(e) = control::set_io_priority(*target, *io_class, level) {
+                                    failures.push(format!("I/O priority of {}: {}", target, e));
+                                }
+                            }
+                        }
+                        // Permission errors are shown in the popup so the values can be adjusted
+                        if failures.is_empty() {
+                            state.message = Some(format!("Changed the priority of {} ({}) and {} descendants", name, pid, pids.len() - 1));
+                            close = true;
+                        } else {
+                            let attempts = pids.len() * (nice_changed as usize + io_changed as usize);
+                            *error = Some(format!("Failed to change {} of {}: {}", failures.len(), attempts, failures[0]));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        },
+        None => {},
+    }
+    if close {
+        state.popup = None;
+    }
+}
+
+/// Creates the priority popup for the process, prefilled with its current values
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `name` - The name of the process
+fn priority_popup(pid: u32, name: String) -> Popup {
+    let nice = control::get_nice(pid);
+    let io_priority = control::get_io_priority(pid);
+    let current = format!(
+        "nice {}, I/O {}",
+        nice.as_ref().map(|nice| nice.to_string()).unwrap_or_else(|e| e.to_string()),
+        io_priority.as_ref().map(|priority| control::io_priority_name(*priority)).unwrap_or_else(|e| e.to_string()),
+    );
+    // Processes without an I/O class get their best-effort level from the nice value, it
+    // is the level shown if the class is changed
+    let nice = nice.unwrap_or(0);
+    let (io_class, io_level) = match io_priority {
+        Ok((0, _)) | Err(_) => (0, ((nice + 20) / 5) as u32),
+        Ok(priority) => priority,
+    };
+    Popup::Priority {
+        pid,
+        name,
+        field: 0,
+        nice,
+        io_class,
+        io_level,
+        subtree: false,
+        current,
+        initial: (nice, io_class, io_level),
+        error: None,
+    }
 }
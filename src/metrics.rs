@@ -0,0 +1,312 @@
+/// Contains the memory split into the parts shown in the stacked memory bar, they add up
+/// to the total memory
+/// ### Fields
+/// * `used` - The used memory that isn't shared
+/// * `shared` - The shared memory and tmpfs contents
+/// * `buffers` - The block device buffers that can be reclaimed
+/// * `cache` - The page cache and slab that can be reclaimed
+/// * `free` - The memory that isn't used at all
+#[derive(Debug, PartialEq)]
+pub struct MemoryBreakdown {
+    pub used: u64,
+    pub shared: u64,
+    pub buffers: u64,
+    pub cache: u64,
+    pub free: u64,
+}
+
+/// Returns the part as a percentage of the total, 0 if the total is 0
+/// ### Parameters
+/// * `part` - The part of the total
+/// * `total` - The total
+pub fn percentage(part: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0_f32;
+    }
+    (part.min(total) as f64 / total as f64 * 100_f64) as f32
+}
+
+/// Returns the used memory like `free` and `htop` do, everything except the memory available
+/// for new allocations, falls back to everything except the free memory when the kernel
+/// doesn't report the available memory
+/// ### Parameters
+/// * `total` - The total memory
+/// * `available` - The `MemAvailable` of `/proc/meminfo`, 0 if unknown
+/// * `free` - The free memory
+pub fn memory_used(total: u64, available: u64, free: u64) -> u64 {
+    if available > 0 {
+        total.saturating_sub(available)
+    } else {
+        total.saturating_sub(free)
+    }
+}
+
+/// Splits the memory into used, shared, buffer, cache and free memory, the used and shared
+/// memory add up to the memory that isn't available and the buffers and cache to the
+/// available memory that isn't free
+/// ### Parameters
+/// * `total` - The total memory
+/// * `unused` - The `MemFree` of `/proc/meminfo`
+/// * `available` - The `MemAvailable` of `/proc/meminfo`, 0 if unknown
+/// * `buffers` - The `Buffers` of `/proc/meminfo`
+/// * `shared` - The `Shmem` of `/proc/meminfo`
+pub fn memory_breakdown(total: u64, unused: u64, available: u64, buffers: u64, shared: u64) -> MemoryBreakdown {
+    let free = unused.min(total);
+    let not_available = memory_used(total, available, free);
+    let shared = shared.min(not_available);
+    // Whatever is available but not free is memory the kernel can reclaim
+    let reclaimable = total.saturating_sub(not_available).saturating_sub(free);
+    let buffers = buffers.min(reclaimable);
+    MemoryBreakdown {
+        used: not_available - shared,
+        shared,
+        buffers,
+        cache: reclaimable - buffers,
+        free: total.saturating_sub(not_available + reclaimable),
+    }
+}
+
+/// Returns the used space of a filesystem like `df` does, the blocks reserved for root count
+/// as used as regular users can't use them
+/// ### Parameters
+/// * `total` - The size of the filesystem
+/// * `available` - The space available to regular users
+pub fn disk_used(total: u64, available: u64) -> u64 {
+    total.saturating_sub(available)
+}
+
+/// Returns the change of a cumulative counter per second, 0 if the counter went backwards
+/// because it was reset or wrapped around
+/// ### Parameters
+/// * `previous` - The previous value of the counter
+/// * `current` - The current value of the counter
+/// * `seconds` - The seconds between the values
+pub fn rate(previous: u64, current: u64, seconds: f64) -> f64 {
+    if seconds <= 0_f64 {
+        return 0_f64;
+    }
+    current.saturating_sub(previous) as f64 / seconds
+}
+
+/// Returns the CPU usage percentage of a process or thread from the clock ticks it consumed,
+/// 100% being one full core
+/// ### Parameters
+/// * `previous` - The previously consumed clock ticks
+/// * `current` - The currently consumed clock ticks
+/// * `ticks_per_second` - The clock ticks per second of the kernel
+/// * `seconds` - The seconds between the values
+pub fn ticks_percentage(previous: u64, current: u64, ticks_per_second: u64, seconds: f64) -> f32 {
+    if ticks_per_second == 0 {
+        return 0_f32;
+    }
+    (rate(previous, current, seconds) / ticks_per_second as f64 * 100_f64) as f32
+}
+
+/// Returns the average time a block device request took in milliseconds, 0 without requests
+/// ### Parameters
+/// * `ticks` - The milliseconds spent on the completed requests
+/// * `requests` - The count of completed requests
+pub fn average_latency(ticks: u64, requests: u64) -> f32 {
+    if requests == 0 {
+        return 0_f32;
+    }
+    (ticks as f64 / requests as f64) as f32
+}
+
+/// Returns the percentage of the interval a block device was busy, at most 100%
+/// ### Parameters
+/// * `busy_millis` - The milliseconds the device had requests in flight
+/// * `seconds` - The length of the interval in seconds
+pub fn utilisation(busy_millis: u64, seconds: f64) -> f32 {
+    if seconds <= 0_f64 {
+        return 0_f32;
+    }
+    (busy_millis as f64 / (seconds * 1000_f64) * 100_f64).min(100_f64) as f32
+}
+
+/// Returns the CPU usage percentage from the idle fractions, where 1.0 means the core was
+/// idle the whole time, time waiting for I/O counts as idle as the core could run other tasks
+/// ### Parameters
+/// * `idle` - The fraction of time the core was idle
+/// * `iowait` - The fraction of time the core was idle while there was I/O in flight
+pub fn cpu_usage(idle: f32, iowait: f32) -> f32 {
+    ((1_f32 - idle - iowait) * 100_f32).clamp(0_f32, 100_f32)
+}
+
+/// Returns the fractions of time a core spent in each state of `/proc/stat` between two
+/// samples, the guest time is taken out of the user and nice time which include it so
+/// that the fractions add up to 1.0
+/// ### Parameters
+/// * `previous` - The previous cumulative times in `/proc/stat` column order, user, nice,
+///   system, idle, iowait, irq, softirq, steal, guest and guest_nice
+/// * `current` - The current cumulative times in the same order
+pub fn cpu_time_fractions(previous: &[u64; 10], current: &[u64; 10]) -> [f32; 10] {
+    let mut deltas = [0_u64; 10];
+    for i in 0..10 {
+        // The idle and iowait counters of offline or hotplugged cores can go backwards
+        deltas[i] = current[i].saturating_sub(previous[i]);
+    }
+    deltas[0] = deltas[0].saturating_sub(deltas[8]);
+    deltas[1] = deltas[1].saturating_sub(deltas[9]);
+    let total: u64 = deltas.iter().sum();
+    let mut fractions = [0_f32; 10];
+    if total == 0 {
+        // A core that didn't tick at all was idle
+        fractions[3] = 1_f32;
+        return fractions;
+    }
+    for i in 0..10 {
+        fractions[i] = (deltas[i] as f64 / total as f64) as f32;
+    }
+    fractions
+}
+
+/// Returns the average CPU usage percentage of all cores, 0 without cores
+/// ### Parameters
+/// * `usages` - The CPU usage percentages of the cores
+pub fn cpu_total(usages: &[f32]) -> f32 {
+    if usages.is_empty() {
+        return 0_f32;
+    }
+    usages.iter().sum::<f32>() / usages.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn percentage_of_total() {
+        assert_eq!(percentage(25, 100), 25_f32);
+        assert_eq!(percentage(0, 100), 0_f32);
+        assert_eq!(percentage(100, 100), 100_f32);
+        assert_eq!(percentage(10, 0), 0_f32);
+        assert_eq!(percentage(200, 100), 100_f32);
+    }
+
+    #[test]
+    fn used_memory_comes_from_available() {
+        assert_eq!(memory_used(8000 * MB, 6000 * MB, 1000 * MB), 2000 * MB);
+        // Kernels older than 3.14 have no MemAvailable
+        assert_eq!(memory_used(8000 * MB, 0, 1000 * MB), 7000 * MB);
+        assert_eq!(memory_used(8000 * MB, 9000 * MB, 1000 * MB), 0);
+    }
+
+    #[test]
+    fn memory_breakdown_adds_up_to_total() {
+        let total = 8000 * MB;
+        let breakdown = memory_breakdown(total, 1000 * MB, 6000 * MB, 200 * MB, 300 * MB);
+        assert_eq!(
+            breakdown,
+            MemoryBreakdown {
+                used: 1700 * MB,
+                shared: 300 * MB,
+                buffers: 200 * MB,
+                cache: 4800 * MB,
+                free: 1000 * MB,
+            }
+        );
+        assert_eq!(breakdown.used + breakdown.shared + breakdown.buffers + breakdown.cache + breakdown.free, total);
+    }
+
+    #[test]
+    fn memory_breakdown_without_meminfo() {
+        let breakdown = memory_breakdown(8000 * MB, 3000 * MB, 0, 0, 0);
+        assert_eq!(breakdown.used, 5000 * MB);
+        assert_eq!(breakdown.cache, 0);
+        assert_eq!(breakdown.free, 3000 * MB);
+    }
+
+    #[test]
+    fn memory_breakdown_clamps_inconsistent_values() {
+        // The fields of /proc/meminfo aren't read atomically
+        let breakdown = memory_breakdown(1000, 1200, 900, 500, 2000);
+        assert_eq!(breakdown.used + breakdown.shared + breakdown.buffers + breakdown.cache + breakdown.free, 1000);
+        assert_eq!(breakdown.free, 900);
+    }
+
+    #[test]
+    fn disk_used_counts_reserved_blocks() {
+        assert_eq!(disk_used(100 * MB, 40 * MB), 60 * MB);
+        assert_eq!(disk_used(100 * MB, 200 * MB), 0);
+        assert_eq!(percentage(disk_used(100 * MB, 40 * MB), 100 * MB), 60_f32);
+    }
+
+    #[test]
+    fn rate_per_second() {
+        assert_eq!(rate(1000, 3000, 2_f64), 1000_f64);
+        assert_eq!(rate(1000, 1000, 1_f64), 0_f64);
+        assert_eq!(rate(3000, 1000, 1_f64), 0_f64);
+        assert_eq!(rate(0, 1000, 0_f64), 0_f64);
+    }
+
+    #[test]
+    fn ticks_percentage_of_a_core() {
+        assert_eq!(ticks_percentage(100, 150, 100, 1_f64), 50_f32);
+        assert_eq!(ticks_percentage(100, 500, 100, 2_f64), 200_f32);
+        assert_eq!(ticks_percentage(100, 150, 0, 1_f64), 0_f32);
+    }
+
+    #[test]
+    fn block_device_derivations() {
+        assert_eq!(average_latency(50, 10), 5_f32);
+        assert_eq!(average_latency(50, 0), 0_f32);
+        assert_eq!(utilisation(250, 0.5_f64), 50_f32);
+        assert_eq!(utilisation(1200, 1_f64), 100_f32);
+        assert_eq!(utilisation(100, 0_f64), 0_f32);
+    }
+
+    #[test]
+    fn cpu_usage_from_idle_fraction() {
+        assert_eq!(cpu_usage(1_f32, 0_f32), 0_f32);
+        assert_eq!(cpu_usage(0_f32, 0_f32), 100_f32);
+        assert_eq!(cpu_usage(0.75_f32, 0_f32), 25_f32);
+        assert_eq!(cpu_usage(0.5_f32, 0.25_f32), 25_f32);
+        assert_eq!(cpu_usage(1.01_f32, 0_f32), 0_f32);
+    }
+
+    #[test]
+    fn cpu_time_fractions_between_samples() {
+        let previous = [100, 10, 50, 1000, 20, 5, 5, 0, 0, 0];
+        let current = [150, 10, 75, 1100, 30, 10, 5, 5, 0, 0];
+        let fractions = cpu_time_fractions(&previous, &current);
+        // 50 user, 25 system, 100 idle, 10 iowait, 5 irq and 5 steal ticks
+        assert_eq!(fractions[0], 50_f32 / 195_f32);
+        assert_eq!(fractions[2], 25_f32 / 195_f32);
+        assert_eq!(fractions[3], 100_f32 / 195_f32);
+        assert_eq!(fractions[4], 10_f32 / 195_f32);
+        assert_eq!(fractions[7], 5_f32 / 195_f32);
+        assert!((fractions.iter().sum::<f32>() - 1_f32).abs() < 0.0001);
+    }
+
+    #[test]
+    fn cpu_time_fractions_take_guest_out_of_user() {
+        let previous = [0; 10];
+        let current = [80, 20, 0, 100, 0, 0, 0, 0, 60, 10];
+        let fractions = cpu_time_fractions(&previous, &current);
+        assert_eq!(fractions[0], 0.1_f32);
+        assert_eq!(fractions[1], 0.05_f32);
+        assert_eq!(fractions[8], 0.3_f32);
+        assert_eq!(fractions[9], 0.05_f32);
+        assert!((fractions.iter().sum::<f32>() - 1_f32).abs() < 0.0001);
+    }
+
+    #[test]
+    fn cpu_time_fractions_of_a_core_without_ticks() {
+        let times = [10, 0, 10, 100, 0, 0, 0, 0, 0, 0];
+        let fractions = cpu_time_fractions(&times, &times);
+        assert_eq!(fractions[3], 1_f32);
+        assert_eq!(cpu_usage(fractions[3], fractions[4]), 0_f32);
+        // Counters going backwards don't make negative fractions
+        let fractions = cpu_time_fractions(&[10, 0, 10, 200, 0, 0, 0, 0, 0, 0], &times);
+        assert_eq!(fractions[3], 1_f32);
+    }
+
+    #[test]
+    fn cpu_total_is_the_average_of_the_cores() {
+        assert_eq!(cpu_total(&[100_f32, 0_f32, 50_f32, 50_f32]), 50_f32);
+        assert_eq!(cpu_total(&[]), 0_f32);
+    }
+}
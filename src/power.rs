@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::sysfs::{read_string, read_value};
+
+/// The sysfs directory with the power supplies
+pub const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// Contains the state of a single battery or AC adapter
+/// ### Fields
+/// * `name` - The name of the power supply, such as `BAT0` or `AC`
+/// * `kind` - The type of the power supply, such as `Battery`, `Mains` or `USB`
+/// * `system` - Whether the power supply powers the system rather than a peripheral like a mouse
+/// * `online` - Whether an adapter is plugged in, `None` for batteries
+/// * `status` - The charging state of a battery, such as `Charging`, `Discharging` or `Full`
+/// * `capacity` - The charge of a battery in percent
+/// * `power` - The rate the battery is charged or discharged at in watts
+/// * `time_to_empty` - The estimated time until the battery is empty while discharging
+/// * `time_to_full` - The estimated time until the battery is full while charging
+/// * `health` - The full capacity of the battery as a percentage of its design capacity
+/// * `cycles` - The charge cycle count of the battery
+pub struct PowerSupply {
+    pub name: String,
+    pub kind: String,
+    pub system: bool,
+    pub online: Option<bool>,
+    pub status: Option<String>,
+    pub capacity: Option<f32>,
+    pub power: Option<f32>,
+    pub time_to_empty: Option<Duration>,
+    pub time_to_full: Option<Duration>,
+    pub health: Option<f32>,
+    pub cycles: Option<u64>,
+}
+
+impl PowerSupply {
+    /// Checks if the power supply is a battery
+    pub fn is_battery(&self) -> bool {
+        self.kind == "Battery"
+    }
+}
+
+/// Reads every power supply in name order, returns none if the directory doesn't exist
+/// like on most desktops and virtual machines
+/// ### Parameters
+/// * `root` - The power supply class directory, `POWER_SUPPLY` outside of tests
+pub fn read_power_supplies(root: &Path) -> Vec<PowerSupply> {
+    let mut names: Vec<String> = match fs::read_dir(root) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.file_name().to_string_lossy().into_owned()).collect(),
+        Err(_) => return Vec::new(),
+    };
+    names.sort();
+    names.iter().filter_map(|name| read_power_supply(&root.join(name), name)).collect()
+}
+
+/// Reads a single power supply, the energy values are in µWh and µW and the charge
+/// values in µAh and µA depending on what the driver reports
+/// ### Parameters
+/// * `directory` - The directory of the power supply
+/// * `name` - The name of the power supply
+fn read_power_supply(directory: &Path, name: &str) -> Option<PowerSupply> {
+    let kind = read_string(&directory.join("type"))?;
+    let value = |attribute: &str| read_value::<f64>(&directory.join(attribute));
+    let status = read_string(&directory.join("status"));
+    // Batteries report either energy or charge, a charge times the voltage is an energy
+    let voltage = value("voltage_now").map(|voltage| voltage / 1000000_f64);
+    let energy = |attribute: &str| -> Option<f64> {
+        value(&format!("energy_{}", attribute))
+            .or_else(|| Some(value(&format!("charge_{}", attribute))? * voltage?))
+            .map(|energy| energy / 1000000_f64)
+    };
+    let (now, full, design) = (energy("now"), energy("full"), energy("full_design"));
+    let power = value("power_now")
+        .or_else(|| Some(value("current_now")? * voltage?))
+        .map(|power| power.abs() / 1000000_f64)
+        .filter(|power| *power > 0_f64);
+    let capacity = value("capacity").or_else(|| Some(now? / full.filter(|full| *full > 0_f64)? * 100_f64));
+    // Some drivers report signed values, a negative or endless time is no estimate
+    let hours = |energy: Option<f64>| -> Option<Duration> { Duration::try_from_secs_f64(energy? / power? * 3600_f64).ok() };
+    let (time_to_empty, time_to_full) = match status.as_deref() {
+        Some("Discharging") => (
+            value("time_to_empty_now").map(|seconds| Duration::from_secs(seconds as u64)).or_else(|| hours(now)),
+            None,
+        ),
+        Some("Charging") => (
+            None,
+            value("time_to_full_now")
+                .map(|seconds| Duration::from_secs(seconds as u64))
+                .or_else(|| hours(Some(full? - now?).filter(|energy| *energy >= 0_f64))),
+        ),
+        _ => (None, None),
+    };
+    Some(PowerSupply {
+        name: name.to_string(),
+        system: read_string(&directory.join("scope")).map_or(true, |scope| scope != "Device"),
+        online: if kind == "Battery" { None } else { value("online").map(|online| online > 0_f64) },
+        kind,
+        status,
+        capacity: capacity.map(|capacity| capacity.min(100_f64) as f32),
+        power: power.map(|power| power as f32),
+        time_to_empty,
+        time_to_full,
+        health: design.filter(|design| *design > 0_f64).and_then(|design| Some((full? / design * 100_f64) as f32)),
+        cycles: value("cycle_count").filter(|cycles| *cycles > 0_f64).map(|cycles| cycles as u64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+    use std::env;
+
+    #[test]
+    fn missing_directory_has_no_power_supplies() {
+        assert!(read_power_supplies(&env::temp_dir().join("rctop-power-does-not-exist")).is_empty());
+    }
+
+    #[test]
+    fn reads_an_energy_battery_and_an_adapter() {
+        let sysfs = FakeSysfs::new("power-energy");
+        sysfs.write("AC/type", "Mains");
+        sysfs.write("AC/online", "0");
+        sysfs.write("BAT0/type", "Battery");
+        sysfs.write("BAT0/status", "Discharging");
+        sysfs.write("BAT0/capacity", "50");
+        sysfs.write("BAT0/energy_now", "20000000");
+        sysfs.write("BAT0/energy_full", "40000000");
+        sysfs.write("BAT0/energy_full_design", "50000000");
+        sysfs.write("BAT0/power_now", "10000000");
+        sysfs.write("BAT0/cycle_count", "321");
+
+        let supplies = read_power_supplies(&sysfs.root);
+        assert_eq!(supplies.len(), 2);
+        assert_eq!(supplies[0].name, "AC");
+        assert_eq!(supplies[0].online, Some(false));
+        assert!(!supplies[0].is_battery());
+        let battery = &supplies[1];
+        assert!(battery.is_battery() && battery.system);
+        assert_eq!(battery.online, None);
+        assert_eq!(battery.status.as_deref(), Some("Discharging"));
+        assert_eq!(battery.capacity, Some(50_f32));
+        assert_eq!(battery.power, Some(10_f32));
+        assert_eq!(battery.time_to_empty, Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(battery.time_to_full, None);
+        assert_eq!(battery.health, Some(80_f32));
+        assert_eq!(battery.cycles, Some(321));
+    }
+
+    #[test]
+    fn reads_a_charge_battery() {
+        let sysfs = FakeSysfs::new("power-charge");
+        sysfs.write("BAT1/type", "Battery");
+        sysfs.write("BAT1/status", "Charging");
+        sysfs.write("BAT1/voltage_now", "10000000");
+        sysfs.write("BAT1/charge_now", "1000000");
+        sysfs.write("BAT1/charge_full", "3000000");
+        sysfs.write("BAT1/current_now", "2000000");
+        sysfs.write("BAT1/cycle_count", "0");
+
+        let supplies = read_power_supplies(&sysfs.root);
+        let battery = &supplies[0];
+        // 1 Ah of 3 Ah at 10 V, charged with 2 A
+        assert!((battery.capacity.unwrap() - 33.333_f32).abs() < 0.01);
+        assert_eq!(battery.power, Some(20_f32));
+        assert_eq!(battery.time_to_full, Some(Duration::from_secs(3600)));
+        assert_eq!(battery.time_to_empty, None);
+        assert_eq!(battery.health, None);
+        assert_eq!(battery.cycles, None);
+    }
+
+    #[test]
+    fn negative_charges_have_no_time_estimate() {
+        let sysfs = FakeSysfs::new("power-negative");
+        sysfs.write("BAT0/type", "Battery");
+        sysfs.write("BAT0/status", "Discharging");
+        sysfs.write("BAT0/voltage_now", "10000000");
+        sysfs.write("BAT0/charge_now", "-1000000");
+        sysfs.write("BAT0/current_now", "2000000");
+        let supplies = read_power_supplies(&sysfs.root);
+        assert_eq!(supplies[0].time_to_empty, None);
+    }
+
+    #[test]
+    fn peripheral_batteries_are_not_system_batteries() {
+        let sysfs = FakeSysfs::new("power-peripheral");
+        sysfs.write("hidpp_battery_0/type", "Battery");
+        sysfs.write("hidpp_battery_0/scope", "Device");
+        sysfs.write("hidpp_battery_0/capacity", "90");
+        sysfs.write("not_a_supply/uevent", "");
+        let supplies = read_power_supplies(&sysfs.root);
+        assert_eq!(supplies.len(), 1);
+        assert!(!supplies[0].system);
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+/// Contains the fields of `/proc/[pid]/stat` that rctop uses
+/// ### Fields
+/// * `comm` - The executable name of the process
+/// * `state` - The state of the process (R, S, D, Z, T etc.)
+/// * `ppid` - The process id of the parent
+/// * `utime` - The time spent in user mode in clock ticks
+/// * `stime` - The time spent in kernel mode in clock ticks
+/// * `nice` - The nice value of the process
+/// * `num_threads` - The thread count of the process
+/// * `starttime` - The time the process started after boot in clock ticks
+/// * `vsize` - The virtual memory size in bytes
+/// * `rss` - The resident set size in pages
+/// * `processor` - The CPU core the process last ran on
+pub struct ProcStat {
+    pub comm: String,
+    pub state: char,
+    pub ppid: u32,
+    pub utime: u64,
+    pub stime: u64,
+    pub nice: i64,
+    pub num_threads: u64,
+    pub starttime: u64,
+    pub vsize: u64,
+    pub rss: u64,
+    pub processor: u32,
+}
+
+/// Lists the ids of all processes currently in `/proc`
+pub fn list_pids() -> io::Result<Vec<u32>> {
+    let mut pids: Vec<u32> = Vec::new();
+    for entry in fs::read_dir("/proc")? {
+        if let Some(pid) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+            pids.push(pid);
+        }
+    }
+    Ok(pids)
+}
+
+/// Reads and parses `/proc/[pid]/stat`
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_stat(pid: u32) -> io::Result<ProcStat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid))?)
+}
+
+/// Parses the contents of a `stat` file, the executable name is enclosed in
+/// parentheses and may itself contain spaces and parentheses
+/// ### Parameters
+/// * `content` - The contents of the stat file
+pub fn parse_stat(content: &str) -> io::Result<ProcStat> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed stat file");
+    let open = content.find('(').ok_or_else(invalid)?;
+    let close = content.rfind(')').ok_or_else(invalid)?;
+    // The pid is known from the path, it only tells that the line is a stat line
+    content[..open].trim().parse::<u32>().map_err(|_| invalid())?;
+    let comm = content[open + 1..close].to_string();
+    // Fields after the name, starting from the state which is field 3 in proc(5)
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    if fields.len() < 37 {
+        return Err(invalid());
+    }
+    let field = |index: usize| -> u64 { fields[index - 3].parse().unwrap_or(0) };
+    Ok(ProcStat {
+        comm,
+        state: fields[0].chars().next().unwrap_or('?'),
+        ppid: field(4) as u32,
+        utime: field(14),
+        stime: field(15),
+        nice: fields[19 - 3].parse().unwrap_or(0),
+        num_threads: field(20),
+        starttime: field(22),
+        vsize: field(23),
+        rss: field(24),
+        processor: field(39) as u32,
+    })
+}
+
+/// Reads `/proc/[pid]/status` into a map of field names and their raw values
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_status(pid: u32) -> io::Result<HashMap<String, String>> {
+    let content = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    let mut status: HashMap<String, String> = HashMap::new();
+    for line in content.lines() {
+        if let Some(index) = line.find(':') {
+            status.insert(line[..index].to_string(), line[index + 1..].trim().to_string());
+        }
+    }
+    Ok(status)
+}
+
+/// Reads the command line of the process with the arguments separated by spaces,
+/// kernel threads have no command line and return an empty string
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_cmdline(pid: u32) -> io::Result<String> {
+    Ok(read_arguments(pid)?.join(" "))
+}
+
+/// Reads the command line arguments of the process, kernel threads have none
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_arguments(pid: u32) -> io::Result<Vec<String>> {
+    let content = fs::read(format!("/proc/{}/cmdline", pid))?;
+    Ok(content
+        .split(|byte| *byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect())
+}
+
+/// Reads the boot time of the system in seconds since the unix epoch from `/proc/stat`
+pub fn boot_time() -> io::Result<u64> {
+    let content = fs::read_to_string("/proc/stat")?;
+    content
+        .lines()
+        .find(|line| line.starts_with("btime "))
+        .and_then(|line| line[6..].trim().parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no btime in /proc/stat"))
+}
+
+/// Reads `/proc/meminfo` into a map of field names and their values in bytes,
+/// the page counts of the huge page fields are kept as they are
+pub fn read_meminfo() -> io::Result<HashMap<String, u64>> {
+    let content = fs::read_to_string("/proc/meminfo")?;
+    let mut meminfo: HashMap<String, u64> = HashMap::new();
+    for line in content.lines() {
+        if let Some(index) = line.find(':') {
+            let mut value = line[index + 1..].split_whitespace();
+            if let Some(number) = value.next().and_then(|number| number.parse::<u64>().ok()) {
+                let number = if value.next() == Some("kB") { number * 1024 } else { number };
+                meminfo.insert(line[..index].to_string(), number);
+            }
+        }
+    }
+    Ok(meminfo)
+}
+
+/// Contains the contents of `/proc/loadavg`
+/// ### Fields
+/// * `one` - The load average over the last minute
+/// * `five` - The load average over the last 5 minutes
+/// * `fifteen` - The load average over the last 15 minutes
+/// * `running` - The count of runnable tasks
+/// * `total` - The count of all tasks
+pub struct LoadAvg {
+    pub one: f32,
+    pub five: f32,
+    pub fifteen: f32,
+    pub running: u64,
+    pub total: u64,
+}
+
+/// Reads and parses `/proc/loadavg`
+pub fn read_loadavg() -> io::Result<LoadAvg> {
+    let content = fs::read_to_string("/proc/loadavg")?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed /proc/loadavg");
+    let fields: Vec<&str> = content.split_whitespace().collect();
+    if fields.len() < 4 {
+        return Err(invalid());
+    }
+    let (running, total) = fields[3].split_once('/').ok_or_else(invalid)?;
+    Ok(LoadAvg {
+        one: fields[0].parse().map_err(|_| invalid())?,
+        five: fields[1].parse().map_err(|_| invalid())?,
+        fifteen: fields[2].parse().map_err(|_| invalid())?,
+        running: running.parse().map_err(|_| invalid())?,
+        total: total.parse().map_err(|_| invalid())?,
+    })
+}
+
+/// The count of CPU time columns in `/proc/stat`, user, nice, system, idle, iowait,
+/// irq, softirq, steal, guest and guest_nice
+pub const CPU_TIME_FIELDS: usize = 10;
+
+/// Reads the cumulative CPU time of every core in clock ticks from `/proc/stat`,
+/// the columns missing on older kernels are 0
+pub fn read_cpu_times() -> io::Result<Vec<[u64; CPU_TIME_FIELDS]>> {
+    let content = fs::read_to_string("/proc/stat")?;
+    let mut cores: Vec<[u64; CPU_TIME_FIELDS]> = Vec::new();
+    // The first line is the sum of all cores, named just "cpu"
+    for line in content.lines().filter(|line| line.starts_with("cpu") && !line.starts_with("cpu ")) {
+        let mut times = [0_u64; CPU_TIME_FIELDS];
+        for (time, value) in times.iter_mut().zip(line.split_whitespace().skip(1)) {
+            *time = value.parse().unwrap_or(0);
+        }
+        cores.push(times);
+    }
+    Ok(cores)
+}
+
+/// Reads the user names of the system from `/etc/passwd` mapped by their user id
+pub fn user_names() -> HashMap<u32, String> {
+    let mut users: HashMap<u32, String> = HashMap::new();
+    if let Ok(content) = fs::read_to_string("/etc/passwd") {
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() > 2 {
+                if let Ok(uid) = fields[2].parse() {
+                    users.insert(uid, fields[0].to_string());
+                }
+            }
+        }
+    }
+    users
+}
+
+/// Returns the kernel clock ticks per second used by the time fields in `/proc`
+pub fn clock_ticks() -> u64 {
+    #[cfg(unix)]
+    {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as u64;
+        }
+    }
+    100
+}
+
+/// Returns the size of a memory page in bytes
+pub fn page_size() -> u64 {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as u64;
+        }
+    }
+    4096
+}
+
+/// Contains a single line of `/proc/[pid]/limits`
+/// ### Fields
+/// * `name` - The name of the resource
+/// * `soft` - The soft limit, `unlimited` or a number
+/// * `hard` - The hard limit, `unlimited` or a number
+/// * `units` - The unit of the limits, empty for counts
+pub struct Limit {
+    pub name: String,
+    pub soft: String,
+    pub hard: String,
+    pub units: String,
+}
+
+/// Contains a single memory mapping of `/proc/[pid]/maps`
+/// ### Fields
+/// * `size` - The size of the mapping in bytes
+/// * `perms` - The permissions of the mapping, such as `r-xp`
+/// * `path` - The mapped file or a pseudo path like `[heap]`, empty for anonymous mappings
+pub struct Mapping {
+    pub size: u64,
+    pub perms: String,
+    pub path: String,
+}
+
+/// Reads the environment variables the process was started with
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_environ(pid: u32) -> io::Result<Vec<String>> {
+    let content = fs::read(format!("/proc/{}/environ", pid))?;
+    Ok(content
+        .split(|byte| *byte == 0)
+        .filter(|variable| !variable.is_empty())
+        .map(|variable| String::from_utf8_lossy(variable).into_owned())
+        .collect())
+}
+
+/// Reads the target of a symbolic link in the directory of the process, such as `cwd` or `exe`
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `name` - The path of the link relative to `/proc/[pid]`
+pub fn read_link(pid: u32, name: &str) -> io::Result<String> {
+    Ok(fs::read_link(format!("/proc/{}/{}", pid, name))?.to_string_lossy().into_owned())
+}
+
+/// Reads the open file descriptors of the process and what they point to, sorted by number
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_fds(pid: u32) -> io::Result<Vec<(u32, String)>> {
+    let mut fds: Vec<(u32, String)> = Vec::new();
+    for entry in fs::read_dir(format!("/proc/{}/fd", pid))? {
+        let entry = entry?;
+        if let Some(fd) = entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+            // The descriptor can be closed between listing and reading it
+            let target = fs::read_link(entry.path()).map(|target| target.to_string_lossy().into_owned()).unwrap_or_else(|_| String::from("?"));
+            fds.push((fd, target));
+        }
+    }
+    fds.sort_by_key(|(fd, _)| *fd);
+    Ok(fds)
+}
+
+/// Reads and parses the memory mappings of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_maps(pid: u32) -> io::Result<Vec<Mapping>> {
+    Ok(parse_maps(&fs::read_to_string(format!("/proc/{}/maps", pid))?))
+}
+
+/// Parses the content of `/proc/[pid]/maps`, lines without an address range are skipped
+/// ### Parameters
+/// * `content` - The content of the file
+fn parse_maps(content: &str) -> Vec<Mapping> {
+    let mut mappings: Vec<Mapping> = Vec::new();
+    for line in content.lines() {
+        // address perms offset dev inode path, the path may contain spaces
+        let mut fields = line.splitn(6, ' ');
+        let range = fields.next().unwrap_or("");
+        let perms = fields.next().unwrap_or("");
+        let path = fields.nth(3).unwrap_or("").trim();
+        let (start, end) = match range.find('-') {
+            Some(index) => (
+                u64::from_str_radix(&range[..index], 16).unwrap_or(0),
+                u64::from_str_radix(&range[index + 1..], 16).unwrap_or(0),
+            ),
+            None => continue,
+        };
+        mappings.push(Mapping {
+            size: end.saturating_sub(start),
+            perms: perms.to_string(),
+            path: path.to_string(),
+        });
+    }
+    mappings
+}
+
+/// Reads and parses the resource limits of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_limits(pid: u32) -> io::Result<Vec<Limit>> {
+    Ok(parse_limits(&fs::read_to_string(format!("/proc/{}/limits", pid))?))
+}
+
+/// Parses the content of `/proc/[pid]/limits`, the first line is the header
+/// ### Parameters
+/// * `content` - The content of the file
+fn parse_limits(content: &str) -> Vec<Limit> {
+    // The kernel prints the columns with fixed widths and the names contain spaces
+    let column = |line: &str, start: usize, end: usize| -> String {
+        line.get(start.min(line.len())..end.min(line.len())).unwrap_or("").trim().to_string()
+    };
+    content
+        .lines()
+        .skip(1)
+        .map(|line| Limit {
+            name: column(line, 0, 26),
+            soft: column(line, 26, 47),
+            hard: column(line, 47, 68),
+            units: column(line, 68, line.len()),
+        })
+        .collect()
+}
+
+/// Reads the control groups of the process, one `hierarchy:controllers:path` line per group
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_cgroups(pid: u32) -> io::Result<Vec<String>> {
+    let content = fs::read_to_string(format!("/proc/{}/cgroup", pid))?;
+    Ok(content.lines().map(|line| line.to_string()).collect())
+}
+
+/// Reads the namespaces of the process as their names and identifiers, sorted by name
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn read_namespaces(pid: u32) -> io::Result<Vec<(String, String)>> {
+    let mut namespaces: Vec<(String, String)> = Vec::new();
+    for entry in fs::read_dir(format!("/proc/{}/ns", pid))? {
+        let entry = entry?;
+        let target = fs::read_link(entry.path())?;
+        namespaces.push((entry.file_name().to_string_lossy().into_owned(), target.to_string_lossy().into_owned()));
+    }
+    namespaces.sort();
+    Ok(namespaces)
+}
+
+/// Lists the ids of the threads of the process
+/// ### Parameters
+/// * `pid` - The id of the process
+pub fn list_tids(pid: u32) -> io::Result<Vec<u32>> {
+    let mut tids: Vec<u32> = Vec::new();
+    for entry in fs::read_dir(format!("/proc/{}/task", pid))? {
+        if let Some(tid) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+            tids.push(tid);
+        }
+    }
+    tids.sort();
+    Ok(tids)
+}
+
+/// Reads and parses `/proc/[pid]/task/[tid]/stat`
+/// ### Parameters
+/// * `pid` - The id of the process
+/// * `tid` - The id of the thread
+pub fn read_task_stat(pid: u32, tid: u32) -> io::Result<ProcStat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/task/{}/stat", pid, tid))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A real stat line of a process whose executable is named `a) b (c`
+    const STAT: &str = "4242 (a) b (c) S 1 4242 4242 0 -1 4194560 1203 0 12 0 150 75 0 0 20 -5 3 0 8812 \
+                        24707072 1536 18446744073709551615 1 1 0 0 0 0 0 4096 17475 0 0 0 17 2 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+
+    #[test]
+    fn splits_the_name_at_the_last_parenthesis() {
+        let stat = parse_stat(STAT).unwrap();
+        assert_eq!(stat.comm, "a) b (c");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 1);
+    }
+
+    #[test]
+    fn reads_the_fields_after_the_name() {
+        let stat = parse_stat(STAT).unwrap();
+        assert_eq!(stat.utime, 150);
+        assert_eq!(stat.stime, 75);
+        assert_eq!(stat.nice, -5);
+        assert_eq!(stat.num_threads, 3);
+        assert_eq!(stat.starttime, 8812);
+        assert_eq!(stat.vsize, 24707072);
+        assert_eq!(stat.rss, 1536);
+        assert_eq!(stat.processor, 2);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(parse_stat("").is_err());
+        assert!(parse_stat("4242 (cat R 1 2 3").is_err());
+        assert!(parse_stat("x (cat) R 1 2 3").is_err());
+        // Too few fields after the name
+        assert!(parse_stat("4242 (cat) R 1 4242 4242 0").is_err());
+    }
+
+    #[test]
+    fn reads_the_limit_columns() {
+        let limits = parse_limits(
+            "Limit                     Soft Limit           Hard Limit           Units     \n\
+Max cpu time              unlimited            unlimited            seconds   \n\
+Max stack size            8388608              unlimited            bytes     \n\
+Max processes             24003                24003                processes \n\
+Max nice priority         0                    0                    \n",
+        );
+        let rows: Vec<(&str, &str, &str, &str)> =
+            limits.iter().map(|limit| (limit.name.as_str(), limit.soft.as_str(), limit.hard.as_str(), limit.units.as_str())).collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("Max cpu time", "unlimited", "unlimited", "seconds"),
+                ("Max stack size", "8388608", "unlimited", "bytes"),
+                ("Max processes", "24003", "24003", "processes"),
+                ("Max nice priority", "0", "0", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_size_and_path_of_the_mappings() {
+        let mappings = parse_maps(
+            "55f3541a2000-55f3541a4000 r--p 00000000 fe:00 317783                     /usr/bin/head\n\
+55c8d6a8a000-55c8d6a9a000 rw-p 00000000 00:00 0 \n\
+7ffd1c9e6000-7ffd1ca07000 rw-p 00000000 00:00 0                          [stack]\n\
+7f318c5b5000-7f318c5b8000 r--s 00000000 00:1a 1024                       /tmp/my file (deleted)\n\
+garbage\n",
+        );
+        let rows: Vec<(u64, &str, &str)> = mappings.iter().map(|mapping| (mapping.size, mapping.perms.as_str(), mapping.path.as_str())).collect();
+        assert_eq!(
+            rows,
+            vec![
+                (0x2000, "r--p", "/usr/bin/head"),
+                (0x10000, "rw-p", ""),
+                (0x21000, "rw-p", "[stack]"),
+                (0x3000, "r--s", "/tmp/my file (deleted)"),
+            ]
+        );
+    }
+}
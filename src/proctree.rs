@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::datafetcher::ProcessInfo;
+
+/// Contains a process as a row of the process table
+/// ### Fields
+/// * `process` - The process on the row
+/// * `prefix` - The tree branches drawn before the command, empty in the flat list
+/// * `has_children` - Whether the process has child processes
+/// * `collapsed` - Whether the children of the process are hidden
+/// * `cpu` - The CPU usage of the process, including the hidden children if collapsed
+/// * `rss` - The resident memory of the process, including the hidden children if collapsed
+pub struct ProcessRow<'a> {
+    pub process: &'a ProcessInfo,
+    pub prefix: String,
+    pub has_children: bool,
+    pub collapsed: bool,
+    pub cpu: f32,
+    pub rss: u64,
+}
+
+/// Turns the processes into rows of a flat list in the given order
+/// ### Parameters
+/// * `processes` - The processes in the order they are shown
+pub fn build_list<'a>(processes: &[&'a ProcessInfo]) -> Vec<ProcessRow<'a>> {
+    processes
+        .iter()
+        .map(|process| ProcessRow {
+            process,
+            prefix: String::new(),
+            has_children: false,
+            collapsed: false,
+            cpu: process.cpu,
+            rss: process.rss,
+        })
+        .collect()
+}
+
+/// Nests the processes under their parents, children of collapsed processes are
+/// left out and their usage is added to the collapsed process
+/// ### Parameters
+/// * `processes` - The processes, siblings keep the order they have in this list
+/// * `collapsed` - The ids of the processes whose children are hidden
+pub fn build_tree<'a>(processes: &[&'a ProcessInfo], collapsed: &HashSet<u32>) -> Vec<ProcessRow<'a>> {
+    let pids: HashSet<u32> = processes.iter().map(|process| process.pid).collect();
+    let mut children: HashMap<u32, Vec<&'a ProcessInfo>> = HashMap::new();
+    let mut roots: Vec<&'a ProcessInfo> = Vec::new();
+    for process in processes {
+        // Processes whose parent isn't listed (pid 1, kthreadd or a parent that exited) are roots
+        if process.ppid != process.pid && pids.contains(&process.ppid) {
+            children.entry(process.ppid).or_default().push(process);
+        } else {
+            roots.push(process);
+        }
+    }
+    let mut rows: Vec<ProcessRow<'a>> = Vec::new();
+    let mut visited: HashSet<u32> = HashSet::new();
+    for root in roots {
+        add_subtree(root, "", "", &children, collapsed, &mut visited, &mut rows);
+    }
+    // Processes in a ppid loop are nobody's descendants, the first one listed becomes a root
+    for process in processes {
+        if !visited.contains(&process.pid) {
+            add_subtree(process, "", "", &children, collapsed, &mut visited, &mut rows);
+        }
+    }
+    rows
+}
+
+/// Adds the process and, unless it is collapsed, its descendants to the rows
+/// ### Parameters
+/// * `process` - The process to add
+/// * `branch` - The branch drawn before the process
+/// * `indent` - The branches drawn before the children of the process
+/// * `children` - The child processes per parent id
+/// * `collapsed` - The ids of the processes whose children are hidden
+/// * `visited` - The ids of the processes already added, guards against pid loops
+/// * `rows` - The rows to add to
+fn add_subtree<'a>(
+    process: &'a ProcessInfo,
+    branch: &str,
+    indent: &str,
+    children: &HashMap<u32, Vec<&'a ProcessInfo>>,
+    collapsed: &HashSet<u32>,
+    visited: &mut HashSet<u32>,
+    rows: &mut Vec<ProcessRow<'a>>,
+) {
+    if !visited.insert(process.pid) {
+        return;
+    }
+    let kids: &[&'a ProcessInfo] = children.get(&process.pid).map(|kids| kids.as_slice()).unwrap_or(&[]);
+    let is_collapsed = !kids.is_empty() && collapsed.contains(&process.pid);
+    let (cpu, rss) = if is_collapsed {
+        // The hidden descendants count as added, they aren't roots of their own
+        let mut hidden: HashSet<u32> = HashSet::new();
+        let usage = subtree_usage(process, children, &mut hidden);
+        visited.extend(hidden);
+        usage
+    } else {
+        (process.cpu, process.rss)
+    };
+    rows.push(ProcessRow {
+        process,
+        prefix: branch.to_string(),
+        has_children: !kids.is_empty(),
+        collapsed: is_collapsed,
+        cpu,
+        rss,
+    });
+    if is_collapsed {
+        return;
+    }
+    for (i, child) in kids.iter().enumerate() {
+        let last = i + 1 == kids.len();
+        let child_branch = format!("{}{}", indent, if last { "└─ " } else { "├─ " });
+        let child_indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+        add_subtree(child, &child_branch, &child_indent, children, collapsed, visited, rows);
+    }
+}
+
+/// Sums up the CPU usage and resident memory of the process and all of its descendants
+/// ### Parameters
+/// * `process` - The process at the top of the subtree
+/// * `children` - The child processes per parent id
+/// * `visited` - The ids of the processes already summed up
+fn subtree_usage(process: &ProcessInfo, children: &HashMap<u32, Vec<&ProcessInfo>>, visited: &mut HashSet<u32>) -> (f32, u64) {
+    if !visited.insert(process.pid) {
+        return (0_f32, 0);
+    }
+    let mut cpu = process.cpu;
+    let mut rss = process.rss;
+    for child in children.get(&process.pid).map(|kids| kids.as_slice()).unwrap_or(&[]) {
+        let (child_cpu, child_rss) = subtree_usage(child, children, visited);
+        cpu += child_cpu;
+        rss += child_rss;
+    }
+    (cpu, rss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(list: &[ProcessInfo], collapsed: &[u32]) -> Vec<(u32, String)> {
+        let processes: Vec<&ProcessInfo> = list.iter().collect();
+        let collapsed: HashSet<u32> = collapsed.iter().cloned().collect();
+        build_tree(&processes, &collapsed).iter().map(|row| (row.process.pid, row.prefix.clone())).collect()
+    }
+
+    #[test]
+    fn nests_the_children_under_their_parent() {
+        let list = vec![
+            ProcessInfo::fixture(1, 0, "root", 0.0, 0),
+            ProcessInfo::fixture(2, 1, "root", 0.0, 0),
+            ProcessInfo::fixture(3, 2, "root", 0.0, 0),
+            ProcessInfo::fixture(4, 1, "root", 0.0, 0),
+        ];
+        assert_eq!(
+            rows(&list, &[]),
+            vec![
+                (1, String::new()),
+                (2, String::from("├─ ")),
+                (3, String::from("│  └─ ")),
+                (4, String::from("└─ ")),
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsed_processes_sum_up_their_hidden_children() {
+        let list = [
+            ProcessInfo::fixture(1, 0, "root", 1.0, 10),
+            ProcessInfo::fixture(2, 1, "root", 2.0, 20),
+            ProcessInfo::fixture(3, 2, "root", 4.0, 40),
+            ProcessInfo::fixture(4, 1, "root", 8.0, 80),
+        ];
+        let processes: Vec<&ProcessInfo> = list.iter().collect();
+        let collapsed: HashSet<u32> = [2].iter().cloned().collect();
+        let tree = build_tree(&processes, &collapsed);
+        assert_eq!(tree.iter().map(|row| row.process.pid).collect::<Vec<u32>>(), vec![1, 2, 4]);
+        assert!(tree[1].collapsed && tree[1].has_children);
+        assert_eq!((tree[1].cpu, tree[1].rss), (6.0, 60));
+        // The visible processes keep their own usage
+        assert_eq!((tree[0].cpu, tree[0].rss), (1.0, 10));
+        // A collapsed process without children isn't marked as collapsed
+        let collapsed: HashSet<u32> = [4].iter().cloned().collect();
+        assert!(!build_tree(&processes, &collapsed)[3].collapsed);
+    }
+
+    #[test]
+    fn orphans_and_ppid_loops_become_roots() {
+        // The parent of 3 exited, 5 and 6 are each other's parent
+        let list = vec![
+            ProcessInfo::fixture(1, 0, "root", 0.0, 0),
+            ProcessInfo::fixture(3, 2, "root", 0.0, 0),
+            ProcessInfo::fixture(5, 6, "root", 1.0, 1),
+            ProcessInfo::fixture(6, 5, "root", 2.0, 2),
+            ProcessInfo::fixture(7, 3, "root", 0.0, 0),
+        ];
+        assert_eq!(
+            rows(&list, &[]),
+            vec![(1, String::new()), (3, String::new()), (7, String::from("└─ ")), (5, String::new()), (6, String::from("└─ "))]
+        );
+        let processes: Vec<&ProcessInfo> = list.iter().collect();
+        let collapsed: HashSet<u32> = [5].iter().cloned().collect();
+        let tree = build_tree(&processes, &collapsed);
+        assert_eq!((tree[3].process.pid, tree[3].cpu, tree[3].rss), (5, 3.0, 3));
+        assert_eq!(tree.len(), 4);
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::datafetcher::SystemData;
+
+/// The wait before the first retry after a failed push, doubled after every failure
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait between the retries
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long connecting and sending may take before the push counts as failed
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The count of refreshes kept while the endpoint is unreachable, older ones are dropped
+const MAX_PENDING: usize = 300;
+
+/// The largest UDP datagram sent, small enough to not be fragmented on most links
+const MAX_DATAGRAM: usize = 1400;
+
+/// The formats of the pushed metrics
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PushFormat {
+    /// The InfluxDB line protocol, one line per measurement with the fields and tags
+    Influx,
+    /// The Graphite plaintext protocol, one line per value
+    Graphite,
+}
+
+impl PushFormat {
+    /// Returns the format with the name
+    /// ### Parameters
+    /// * `name` - The name of the format, `influx` or `graphite`
+    pub fn from_name(name: &str) -> Option<PushFormat> {
+        match name {
+            "influx" | "influxdb" => Some(PushFormat::Influx),
+            "graphite" => Some(PushFormat::Graphite),
+            _ => None,
+        }
+    }
+}
+
+/// The protocol the metrics are sent over
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Transport {
+    Tcp,
+    Udp,
+}
+
+/// Contains where and how the metrics are pushed
+/// ### Fields
+/// * `transport` - The protocol the metrics are sent over
+/// * `address` - The host and port of the endpoint
+/// * `format` - The format of the metrics
+/// * `tags` - The tags added to every metric, the host first
+#[derive(Clone, PartialEq, Debug)]
+pub struct PushTarget {
+    transport: Transport,
+    address: String,
+    format: PushFormat,
+    tags: Vec<(String, String)>,
+}
+
+impl PushTarget {
+    /// Parses the endpoint and the extra tags, returns a description of the problem for
+    /// invalid ones, the host tag is the hostname unless the tags have one
+    /// ### Parameters
+    /// * `url` - The endpoint, `tcp://HOST:PORT` or `udp://HOST:PORT`
+    /// * `format` - The format of the metrics
+    /// * `tags` - The comma separated `KEY=VALUE` tags, if any
+    pub fn parse(url: &str, format: PushFormat, tags: Option<&str>) -> Result<PushTarget, String> {
+        let (transport, address) = if let Some(address) = url.strip_prefix("tcp://") {
+            (Transport::Tcp, address)
+        } else if let Some(address) = url.strip_prefix("udp://") {
+            (Transport::Udp, address)
+        } else {
+            return Err(format!("Invalid push endpoint '{}', use tcp://HOST:PORT or udp://HOST:PORT", url));
+        };
+        let port = address.rsplit_once(':').and_then(|(host, port)| Some(port).filter(|_| !host.is_empty()));
+        if port.and_then(|port| port.parse::<u16>().ok()).is_none() {
+            return Err(format!("The push endpoint '{}' needs a host and a port", url));
+        }
+        let mut parsed: Vec<(String, String)> = Vec::new();
+        for tag in tags.unwrap_or("").split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            match tag.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                    parsed.push((key.trim().to_string(), value.trim().to_string()));
+                }
+                _ => return Err(format!("Invalid push tag '{}', use KEY=VALUE", tag)),
+            }
+        }
+        if !parsed.iter().any(|(key, _)| key == "host") {
+            parsed.insert(0, (String::from("host"), hostname()));
+        }
+        Ok(PushTarget {
+            transport,
+            address: address.to_string(),
+            format,
+            tags: parsed,
+        })
+    }
+}
+
+/// Sends the metrics of every refresh to the endpoint from a background thread so that a
+/// slow or unreachable endpoint doesn't hold up the refreshes, the refreshes that couldn't
+/// be sent are kept and sent once the endpoint is back
+/// ### Fields
+/// * `target` - Where and how the metrics are pushed
+/// * `sender` - Passes the formatted refreshes to the thread
+/// * `error` - Why the latest push failed, cleared once a push succeeds
+/// * `thread` - The thread sending the metrics
+pub struct Pusher {
+    target: PushTarget,
+    sender: Sender<String>,
+    error: Arc<Mutex<Option<String>>>,
+    thread: JoinHandle<()>,
+}
+
+impl Pusher {
+    /// Starts the thread that sends the metrics, the connection is made on the first push
+    /// ### Parameters
+    /// * `target` - Where and how the metrics are pushed
+    pub fn start(target: PushTarget) -> Pusher {
+        let (sender, receiver) = channel::<String>();
+        let error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let thr_error = error.clone();
+        let thr_target = target.clone();
+        let thread = thread::spawn(move || {
+            let mut pending: VecDeque<String> = VecDeque::new();
+            let mut connection: Option<Connection> = None;
+            let mut backoff = MIN_BACKOFF;
+            let mut retry_at = Instant::now();
+            let mut stopping = false;
+            while !stopping {
+                // Waiting for the next retry still takes the new refreshes in
+                let received = if pending.is_empty() {
+                    receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    receiver.recv_timeout(retry_at.saturating_duration_since(Instant::now()))
+                };
+                match received {
+                    Ok(batch) => {
+                        pending.push_back(batch);
+                        if pending.len() > MAX_PENDING {
+                            pending.pop_front();
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // The pending refreshes get a last try without waiting for the retry
+                    Err(RecvTimeoutError::Disconnected) => stopping = true,
+                }
+                if !stopping && Instant::now() < retry_at {
+                    continue;
+                }
+                while let Some(batch) = pending.front() {
+                    let result = match &mut connection {
+                        Some(connection) => connection.send(batch),
+                        None => Connection::open(&thr_target).and_then(|mut opened| {
+                            let result = opened.send(batch);
+                            connection = Some(opened);
+                            result
+                        }),
+                    };
+                    match result {
+                        Ok(()) => {
+                            pending.pop_front();
+                        }
+                        Err(e) => {
+                            *thr_error.lock().unwrap() = Some(format!("{} ({} refreshes waiting)", e, pending.len()));
+                            connection = None;
+                            retry_at = Instant::now() + backoff;
+                            backoff = (backoff * 2).min(MAX_BACKOFF);
+                            break;
+                        }
+                    }
+                }
+                if pending.is_empty() {
+                    backoff = MIN_BACKOFF;
+                    *thr_error.lock().unwrap() = None;
+                }
+            }
+        });
+        Pusher {
+            target,
+            sender,
+            error,
+            thread,
+        }
+    }
+
+    /// Stops the thread once the queued metrics are sent or have had a last try
+    pub fn stop(self) {
+        drop(self.sender);
+        let _ = self.thread.join();
+    }
+
+    /// Queues the metrics of the system data to be sent
+    /// ### Parameters
+    /// * `sys` - The system data to push
+    pub fn push(&self, sys: &SystemData) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let points = points(sys);
+        let batch = match self.target.format {
+            PushFormat::Influx => format_influx(&points, &self.target.tags, now),
+            PushFormat::Graphite => format_graphite(&points, &self.target.tags, now),
+        };
+        // The thread only stops with the pusher
+        let _ = self.sender.send(batch);
+    }
+
+    /// Returns why the latest push failed, none once a push has succeeded again
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+}
+
+/// An open connection to the endpoint
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl Connection {
+    /// Connects to the endpoint, the address is resolved on every connection so that a
+    /// moved endpoint is found again
+    /// ### Parameters
+    /// * `target` - Where the metrics are pushed
+    fn open(target: &PushTarget) -> Result<Connection, String> {
+        let addresses: Vec<SocketAddr> = target
+            .address
+            .to_socket_addrs()
+            .map_err(|e| format!("Can't resolve {}: {}", target.address, e))?
+            .collect();
+        let mut last_error = format!("Can't resolve {}", target.address);
+        for address in addresses {
+            let result = match target.transport {
+                Transport::Tcp => TcpStream::connect_timeout(&address, NETWORK_TIMEOUT).and_then(|stream| {
+                    stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+                    Ok(Connection::Tcp(stream))
+                }),
+                Transport::Udp => {
+                    let local: SocketAddr = if address.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0_u16; 8], 0).into() };
+                    UdpSocket::bind(local).and_then(|socket| {
+                        socket.connect(address)?;
+                        Ok(Connection::Udp(socket))
+                    })
+                }
+            };
+            match result {
+                Ok(connection) => return Ok(connection),
+                Err(e) => last_error = format!("Can't connect to {}: {}", address, e),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sends the lines of a refresh, over UDP in datagrams of whole lines
+    /// ### Parameters
+    /// * `batch` - The lines to send
+    fn send(&mut self, batch: &str) -> Result<(), String> {
+        match self {
+            Connection::Tcp(stream) => stream.write_all(batch.as_bytes()).map_err(|e| format!("Can't send: {}", e)),
+            Connection::Udp(socket) => {
+                for datagram in datagrams(batch, MAX_DATAGRAM) {
+                    socket.send(datagram.as_bytes()).map_err(|e| format!("Can't send: {}", e))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Splits the lines into chunks of whole lines up to the size, a longer line is a chunk of its own
+/// ### Parameters
+/// * `batch` - The lines to split, each ending with a line break
+/// * `size` - The largest size of a chunk in bytes
+fn datagrams(batch: &str, size: usize) -> Vec<&str> {
+    let mut chunks: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for line in batch.split_inclusive('\n') {
+        if end > start && end - start + line.len() > size {
+            chunks.push(&batch[start..end]);
+            start = end;
+        }
+        end += line.len();
+    }
+    if end > start {
+        chunks.push(&batch[start..end]);
+    }
+    chunks
+}
+
+/// A value of a measurement, the integers are kept apart so that the counters stay exact
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum FieldValue {
+    Integer(u64),
+    Float(f64),
+}
+
+/// A measurement of a single thing, such as a core or an interface
+/// ### Fields
+/// * `name` - The name of the measurement, such as `cpu`
+/// * `id` - The tag telling apart the things of the measurement, such as the core, if any
+/// * `tags` - The other tags describing the thing, only sent in the Influx format
+/// * `fields` - The names and values of the measured fields
+struct Point {
+    name: &'static str,
+    id: Option<(&'static str, String)>,
+    tags: Vec<(&'static str, String)>,
+    fields: Vec<(&'static str, FieldValue)>,
+}
+
+impl Point {
+    fn new(name: &'static str, id: Option<(&'static str, String)>, fields: Vec<(&'static str, FieldValue)>) -> Point {
+        Point { name, id, tags: Vec::new(), fields }
+    }
+}
+
+/// Returns the measurements of the system data, the usages are percentages, the sizes are
+/// in bytes and the rates in bytes per second
+/// ### Parameters
+/// * `sys` - The system data to measure
+fn points(sys: &SystemData) -> Vec<Point> {
+    use FieldValue::{Float, Integer};
+    let mut points: Vec<Point> = vec![Point::new(
+        "system",
+        None,
+        vec![
+            ("uptime", Integer(sys.uptime.as_secs())),
+            ("processes", Integer(sys.processes.count as u64)),
+            ("tasks", Integer(sys.cpu.tasks_total)),
+            ("tasks_running", Integer(sys.cpu.tasks_running)),
+        ],
+    )];
+    if let Some(load) = sys.cpu.load_average.last() {
+        points.push(Point::new(
+            "load",
+            None,
+            vec![("load1", Float(load.one as f64)), ("load5", Float(load.five as f64)), ("load15", Float(load.fifteen as f64))],
+        ));
+    }
+    points.push(Point::new("cpu", Some(("core", String::from("total"))), vec![("usage", Float(sys.cpu.total as f64))]));
+    for (i, (load, usage)) in sys.cpu.load.iter().zip(sys.cpu.usage.iter()).enumerate() {
+        points.push(Point::new(
+            "cpu",
+            Some(("core", i.to_string())),
+            vec![
+                ("usage", Float(*usage as f64)),
+                ("user", Float(load.user as f64 * 100_f64)),
+                ("nice", Float(load.nice as f64 * 100_f64)),
+                ("system", Float(load.system as f64 * 100_f64)),
+                ("irq", Float(load.interrupt as f64 * 100_f64)),
+                ("softirq", Float(load.softirq as f64 * 100_f64)),
+                ("iowait", Float(load.iowait as f64 * 100_f64)),
+                ("steal", Float(load.steal as f64 * 100_f64)),
+                ("guest", Float((load.guest + load.guest_nice) as f64 * 100_f64)),
+                ("idle", Float(load.idle as f64 * 100_f64)),
+            ],
+        ));
+    }
+    let ram = &sys.ram;
+    points.push(Point::new(
+        "mem",
+        None,
+        vec![
+            ("total", Integer(ram.total)),
+            ("used", Integer(ram.used)),
+            ("free", Integer(ram.free)),
+            ("available", Integer(ram.available)),
+            ("buffers", Integer(ram.buffers)),
+            ("cached", Integer(ram.cached)),
+            ("used_percent", Float(ram.percentage as f64)),
+            ("swap_total", Integer(ram.swap_total)),
+            ("swap_used", Integer(ram.swap_total.saturating_sub(ram.swap_free))),
+        ],
+    ));
+    let disk = &sys.disk;
+    for i in (0..disk.count).filter(|i| !disk.pseudo[*i]) {
+        let mut point = Point::new(
+            "disk",
+            Some(("path", disk.fs_mounted_on[i].clone())),
+            vec![
+                ("total", Integer(disk.total[i])),
+                ("used", Integer(disk.used[i])),
+                ("free", Integer(disk.free[i])),
+                ("used_percent", Float(disk.percentage[i] as f64)),
+            ],
+        );
+        point.tags = vec![("device", disk.fs_mounted_from[i].clone()), ("fstype", disk.fs_type[i].clone())];
+        points.push(point);
+    }
+    let diskio = &sys.diskio;
+    for i in 0..diskio.count {
+        points.push(Point::new(
+            "diskio",
+            Some(("name", diskio.names[i].clone())),
+            vec![
+                ("read_rate", Integer(diskio.read_rate[i])),
+                ("write_rate", Integer(diskio.write_rate[i])),
+                ("read_iops", Float(diskio.read_iops[i] as f64)),
+                ("write_iops", Float(diskio.write_iops[i] as f64)),
+                ("utilisation", Float(diskio.utilisation[i] as f64)),
+            ],
+        ));
+    }
+    let network = &sys.network;
+    for (i, name) in network.names.iter().enumerate() {
+        let counter = |values: &Vec<u64>| Integer(values.get(i).cloned().unwrap_or(0));
+        points.push(Point::new(
+            "net",
+            Some(("interface", name.clone())),
+            vec![
+                ("bytes_recv", counter(&network.rx)),
+                ("bytes_sent", counter(&network.tx)),
+                ("packets_recv", counter(&network.rx_packets)),
+                ("packets_sent", counter(&network.tx_packets)),
+                ("err_in", counter(&network.rx_errors)),
+                ("err_out", counter(&network.tx_errors)),
+                ("drop_in", counter(&network.rx_drops)),
+                ("drop_out", counter(&network.tx_drops)),
+                ("recv_rate", counter(&network.rx_rate)),
+                ("sent_rate", counter(&network.tx_rate)),
+            ],
+        ));
+    }
+    points
+}
+
+/// Formats the measurements in the InfluxDB line protocol with nanosecond timestamps
+/// ### Parameters
+/// * `points` - The measurements to format
+/// * `tags` - The tags added to every measurement
+/// * `now` - The time since the unix epoch
+fn format_influx(points: &[Point], tags: &[(String, String)], now: Duration) -> String {
+    let common: String = tags.iter().map(|(key, value)| format!(",{}={}", escape_influx(key, false), escape_influx(value, false))).collect();
+    let mut text = String::new();
+    for point in points {
+        text += &escape_influx(point.name, true);
+        text += &common;
+        for (key, value) in point.id.iter().map(|(key, value)| (*key, value)).chain(point.tags.iter().map(|(key, value)| (*key, value))) {
+            // Empty tag values aren't allowed in the line protocol
+            if !value.is_empty() {
+                text += &format!(",{}={}", key, escape_influx(value, false));
+            }
+        }
+        let fields: Vec<String> = point
+            .fields
+            .iter()
+            .map(|(key, value)| match value {
+                FieldValue::Integer(value) => format!("{}={}i", key, value),
+                FieldValue::Float(value) => format!("{}={}", key, format_float(*value)),
+            })
+            .collect();
+        text += &format!(" {} {}\n", fields.join(","), now.as_nanos());
+    }
+    text
+}
+
+/// Formats the measurements in the Graphite plaintext protocol, the paths start with
+/// `rctop` and the host, the other tags are Graphite tags
+/// ### Parameters
+/// * `points` - The measurements to format
+/// * `tags` - The tags added to every measurement, the host first
+/// * `now` - The time since the unix epoch
+fn format_graphite(points: &[Point], tags: &[(String, String)], now: Duration) -> String {
+    let host = tags.iter().find(|(key, _)| key == "host").map_or("", |(_, value)| value.as_str());
+    let suffix: String = tags
+        .iter()
+        .filter(|(key, _)| key != "host")
+        .map(|(key, value)| format!(";{}={}", graphite_node(key), graphite_node(value)))
+        .collect();
+    let mut text = String::new();
+    for point in points {
+        let mut path = format!("rctop.{}.{}", graphite_node(host), point.name);
+        if let Some((_, id)) = &point.id {
+            path += ".";
+            path += &graphite_node(id);
+        }
+        for (key, value) in &point.fields {
+            let value = match value {
+                FieldValue::Integer(value) => value.to_string(),
+                FieldValue::Float(value) => format_float(*value),
+            };
+            text += &format!("{}.{}{} {} {}\n", path, key, suffix, value, now.as_secs());
+        }
+    }
+    text
+}
+
+/// Escapes a measurement name, tag key or tag value for the line protocol, the names
+/// only need their commas and spaces escaped, the tags also their equal signs
+/// ### Parameters
+/// * `value` - The text to escape
+/// * `measurement` - Whether the text is a measurement name
+fn escape_influx(value: &str, measurement: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            ',' | ' ' => escaped.push('\\'),
+            '=' if !measurement => escaped.push('\\'),
+            '\n' => {
+                escaped.push(' ');
+                continue;
+            }
+            _ => {}
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// Turns a name into a single node of a Graphite path, the root mount is `root` and the
+/// other characters that have a meaning in the path become underscores
+/// ### Parameters
+/// * `name` - The name to turn into a node
+fn graphite_node(name: &str) -> String {
+    if name == "/" {
+        return String::from("root");
+    }
+    let node: String = name
+        .trim_start_matches('/')
+        .chars()
+        .map(|character| if character.is_ascii_alphanumeric() || character == '-' || character == '_' { character } else { '_' })
+        .collect();
+    if node.is_empty() {
+        String::from("_")
+    } else {
+        node
+    }
+}
+
+/// Formats a float without the exponent notation, which both protocols may reject
+/// ### Parameters
+/// * `value` - The value to format
+fn format_float(value: f64) -> String {
+    if value.is_finite() {
+        let text = format!("{:.3}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        String::from("0")
+    }
+}
+
+/// Returns the name of the machine, from the kernel or the environment
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .map(|name| name.trim().to_string())
+        .or_else(|| env::var("HOSTNAME").ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("localhost"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Point> {
+        let mut disk = Point::new("disk", Some(("path", String::from("/mnt/my disk"))), vec![("used", FieldValue::Integer(42))]);
+        disk.tags = vec![("device", String::from("/dev/sda1")), ("fstype", String::new())];
+        vec![Point::new("cpu", Some(("core", String::from("0"))), vec![("usage", FieldValue::Float(12.5)), ("idle", FieldValue::Float(87.5))]), disk]
+    }
+
+    fn tags() -> Vec<(String, String)> {
+        vec![(String::from("host"), String::from("web-1")), (String::from("dc"), String::from("eu west"))]
+    }
+
+    #[test]
+    fn formats_the_line_protocol() {
+        let text = format_influx(&sample(), &tags(), Duration::from_secs(2));
+        assert_eq!(
+            text,
+            "cpu,host=web-1,dc=eu\\ west,core=0 usage=12.5,idle=87.5 2000000000\n\
+             disk,host=web-1,dc=eu\\ west,path=/mnt/my\\ disk,device=/dev/sda1 used=42i 2000000000\n"
+        );
+        assert_eq!(escape_influx("a,b=c d", false), "a\\,b\\=c\\ d");
+        assert_eq!(escape_influx("a=b", true), "a=b");
+    }
+
+    #[test]
+    fn formats_graphite_paths() {
+        let text = format_graphite(&sample(), &tags(), Duration::from_millis(2500));
+        assert_eq!(
+            text,
+            "rctop.web-1.cpu.0.usage;dc=eu_west 12.5 2\n\
+             rctop.web-1.cpu.0.idle;dc=eu_west 87.5 2\n\
+             rctop.web-1.disk.mnt_my_disk.used;dc=eu_west 42 2\n"
+        );
+        assert_eq!(graphite_node("/"), "root");
+        assert_eq!(format_float(1_f64 / 3_f64), "0.333");
+        assert_eq!(format_float(f64::NAN), "0");
+    }
+
+    #[test]
+    fn parses_the_endpoint_and_tags() {
+        let target = PushTarget::parse("udp://127.0.0.1:8089", PushFormat::Influx, Some("host=db, role=primary")).unwrap();
+        assert_eq!(target.transport, Transport::Udp);
+        assert_eq!(target.address, "127.0.0.1:8089");
+        assert_eq!(target.tags, vec![(String::from("host"), String::from("db")), (String::from("role"), String::from("primary"))]);
+        let target = PushTarget::parse("tcp://[::1]:2003", PushFormat::Graphite, None).unwrap();
+        assert_eq!(target.transport, Transport::Tcp);
+        assert_eq!(target.tags[0].0, "host");
+        assert!(PushTarget::parse("http://localhost:8086", PushFormat::Influx, None).is_err());
+        assert!(PushTarget::parse("tcp://localhost", PushFormat::Influx, None).is_err());
+        assert!(PushTarget::parse("tcp://:2003", PushFormat::Influx, None).is_err());
+        assert!(PushTarget::parse("tcp://localhost:2003", PushFormat::Influx, Some("role")).is_err());
+    }
+
+    #[test]
+    fn splits_datagrams_on_whole_lines() {
+        assert_eq!(datagrams("aaa\nbb\ncccc\n", 7), vec!["aaa\nbb\n", "cccc\n"]);
+        assert_eq!(datagrams("aaaaaaaaa\nb\n", 4), vec!["aaaaaaaaa\n", "b\n"]);
+        assert!(datagrams("", 4).is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::datafetcher::SystemData;
+use crate::utils;
+
+/// Appends a row of the metrics to a CSV file on every refresh, the columns of the cores,
+/// disks and interfaces come and go, a new one rewrites the file with the union of the
+/// columns so that the older rows stay aligned with the header
+/// ### Fields
+/// * `path` - The path of the CSV file
+/// * `header` - The columns of the file, empty until the file is first written
+pub struct Recorder {
+    path: PathBuf,
+    header: Vec<String>,
+}
+
+impl Recorder {
+    /// Opens the recording, the rows are appended to the file if it already exists
+    /// ### Parameters
+    /// * `path` - The path of the CSV file
+    pub fn open(path: PathBuf) -> Result<Recorder, Box<dyn Error>> {
+        let header = match fs::read_to_string(&path) {
+            Ok(content) => parse(&content).into_iter().next().unwrap_or_default(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("Can't read {}: {}", path.display(), e).into()),
+        };
+        Ok(Recorder { path, header })
+    }
+
+    /// Appends a row of the system data
+    /// ### Parameters
+    /// * `sys` - The system data to record
+    pub fn record(&mut self, sys: &SystemData) -> Result<(), Box<dyn Error>> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+        self.write_row(&columns(sys, now))
+    }
+
+    /// Appends a row, rewriting the file first when the row has new columns
+    /// ### Parameters
+    /// * `row` - The column names and the values of the row
+    fn write_row(&mut self, row: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+        let header = merge_header(&self.header, row.iter().map(|(name, _)| name.as_str()));
+        if header != self.header || !self.path.exists() {
+            self.rewrite(header).map_err(|e| format!("Can't write {}: {}", self.path.display(), e))?;
+        }
+        let values: Vec<&str> = self
+            .header
+            .iter()
+            .map(|column| row.iter().find(|(name, _)| name == column).map_or("", |(_, value)| value.as_str()))
+            .collect();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Can't open {}: {}", self.path.display(), e))?;
+        // A single write so that an interrupted recording doesn't leave half a row
+        file.write_all(format_row(&values).as_bytes()).map_err(|e| format!("Can't write {}: {}", self.path.display(), e))?;
+        Ok(())
+    }
+
+    /// Rewrites the file with a new header, moving the values of the existing rows to the
+    /// new positions of their columns, the new file replaces the old one once complete
+    /// ### Parameters
+    /// * `header` - The new columns, a superset of the current ones
+    fn rewrite(&mut self, header: Vec<String>) -> Result<(), Box<dyn Error>> {
+        let rows = match fs::read_to_string(&self.path) {
+            Ok(content) => parse(&content).into_iter().skip(1).collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let positions: Vec<Option<usize>> = header.iter().map(|column| self.header.iter().position(|old| old == column)).collect();
+        let header_values: Vec<&str> = header.iter().map(|column| column.as_str()).collect();
+        let mut content = format_row(&header_values);
+        for row in &rows {
+            let values: Vec<&str> = positions
+                .iter()
+                .map(|position| position.and_then(|position| row.get(position)).map_or("", |value| value.as_str()))
+                .collect();
+            content += &format_row(&values);
+        }
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, &self.path)?;
+        self.header = header;
+        Ok(())
+    }
+}
+
+/// Returns the column names and the values of a row, the usages are percentages and the
+/// network rates bytes per second
+/// ### Parameters
+/// * `sys` - The system data to record
+/// * `now` - The current unix time
+fn columns(sys: &SystemData, now: u64) -> Vec<(String, String)> {
+    let (year, month, day, hour, minute, second) = utils::local_time(now);
+    let mut columns: Vec<(String, String)> = vec![
+        (String::from("time"), format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second)),
+        (String::from("timestamp"), now.to_string()),
+        (String::from("cpu"), format!("{:.1}", sys.cpu.total)),
+    ];
+    for (i, usage) in sys.cpu.usage.iter().enumerate() {
+        columns.push((format!("cpu{}", i), format!("{:.1}", usage)));
+    }
+    columns.push((String::from("mem_used"), sys.ram.used.to_string()));
+    columns.push((String::from("mem_percent"), format!("{:.1}", sys.ram.percentage)));
+    let swap_used = sys.ram.swap_total.saturating_sub(sys.ram.swap_free);
+    let swap_percent = if sys.ram.swap_total == 0 { 0_f64 } else { swap_used as f64 / sys.ram.swap_total as f64 * 100_f64 };
+    columns.push((String::from("swap_used"), swap_used.to_string()));
+    columns.push((String::from("swap_percent"), format!("{:.1}", swap_percent)));
+    for i in (0..sys.disk.count).filter(|i| !sys.disk.pseudo[*i]) {
+        columns.push((format!("disk_percent[{}]", sys.disk.fs_mounted_on[i]), format!("{:.1}", sys.disk.percentage[i])));
+    }
+    for (i, name) in sys.network.names.iter().enumerate() {
+        columns.push((format!("net_rx[{}]", name), sys.network.rx_rate.get(i).cloned().unwrap_or(0).to_string()));
+        columns.push((format!("net_tx[{}]", name), sys.network.tx_rate.get(i).cloned().unwrap_or(0).to_string()));
+    }
+    columns
+}
+
+/// Returns the header with the missing columns added, a new column is placed after the
+/// last column of its group, such as `cpu4` after `cpu3` or `net_rx[wlan0]` after
+/// `net_rx[eth0]`, so that the groups stay together in the spreadsheet
+/// ### Parameters
+/// * `header` - The current columns
+/// * `columns` - The columns of the new row
+fn merge_header<'a>(header: &[String], columns: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut merged = header.to_vec();
+    for column in columns {
+        if merged.iter().any(|existing| existing == column) {
+            continue;
+        }
+        match merged.iter().rposition(|existing| group(existing) == group(column)) {
+            Some(position) => merged.insert(position + 1, column.to_string()),
+            None => merged.push(column.to_string()),
+        }
+    }
+    merged
+}
+
+/// Returns the group of a column, its name without the core number or the device
+/// ### Parameters
+/// * `column` - The name of the column
+fn group(column: &str) -> &str {
+    match column.find('[') {
+        Some(bracket) => &column[..bracket],
+        None => column.trim_end_matches(|character: char| character.is_ascii_digit()),
+    }
+}
+
+/// Formats the values as a CSV line, quoting the ones with separators, quotes or line breaks
+/// ### Parameters
+/// * `values` - The values of the line
+fn format_row(values: &[&str]) -> String {
+    let mut line = values
+        .iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// Parses CSV content into its rows, quoted values can contain separators, doubled quotes
+/// and line breaks
+/// ### Parameters
+/// * `content` - The content of the CSV file
+fn parse(content: &str) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                value.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut value)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut value));
+                rows.push(std::mem::take(&mut row));
+            }
+            character => value.push(character),
+        }
+    }
+    // The last line may lack its line break
+    if !value.is_empty() || !row.is_empty() {
+        row.push(value);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn row(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn quotes_and_parses_values() {
+        let line = format_row(&["/mnt/a,b", "say \"hi\"", "plain"]);
+        assert_eq!(line, "\"/mnt/a,b\",\"say \"\"hi\"\"\",plain\n");
+        assert_eq!(parse(&line), vec![vec!["/mnt/a,b", "say \"hi\"", "plain"]]);
+        assert_eq!(parse("a,\"b\nc\"\r\n,d"), vec![vec!["a", "b\nc"], vec!["", "d"]]);
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn new_columns_join_their_group() {
+        let header: Vec<String> = ["time", "cpu", "cpu0", "cpu1", "mem_used", "net_rx[eth0]", "net_tx[eth0]"]
+            .iter()
+            .map(|column| column.to_string())
+            .collect();
+        let merged = merge_header(&header, ["time", "cpu", "cpu0", "cpu2", "mem_used", "net_rx[wlan0]", "disk_percent[/]"].iter().cloned());
+        assert_eq!(
+            merged,
+            vec!["time", "cpu", "cpu0", "cpu1", "cpu2", "mem_used", "net_rx[eth0]", "net_rx[wlan0]", "net_tx[eth0]", "disk_percent[/]"]
+        );
+        assert_eq!(merge_header(&header, header.iter().map(|column| column.as_str())), header);
+    }
+
+    #[test]
+    fn rewrites_the_file_when_columns_appear() {
+        let path = std::env::temp_dir().join(format!("rctop-record-{}.csv", process::id()));
+        let _ = fs::remove_file(&path);
+        let mut recorder = Recorder::open(path.clone()).unwrap();
+        recorder.write_row(&row(&[("time", "1"), ("net_rx[eth0]", "10")])).unwrap();
+        recorder.write_row(&row(&[("time", "2"), ("net_rx[wlan0]", "5")])).unwrap();
+        recorder.write_row(&row(&[("time", "3"), ("net_rx[eth0]", "30"), ("net_rx[wlan0]", "7")])).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "time,net_rx[eth0],net_rx[wlan0]\n1,10,\n2,,5\n3,30,7\n"
+        );
+        // A new recording continues with the columns of the file
+        let mut recorder = Recorder::open(path.clone()).unwrap();
+        recorder.write_row(&row(&[("time", "4"), ("net_rx[wlan0]", "9")])).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().ends_with("3,30,7\n4,,9\n"));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{read_string, read_value};
+
+/// The sysfs directory with the `hwmon` and `thermal` device classes
+pub const SYS_CLASS: &str = "/sys/class";
+
+/// The hwmon drivers and thermal zones that measure the CPU
+const CPU_CHIPS: [&str; 6] = ["coretemp", "k10temp", "zenpower", "cpu_thermal", "x86_pkg_temp", "soc_thermal"];
+
+/// The kinds of hardware sensors
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SensorKind {
+    Temperature,
+    Fan,
+    Voltage,
+    Current,
+    Power,
+}
+
+impl SensorKind {
+    pub const ALL: [SensorKind; 5] = [
+        SensorKind::Temperature,
+        SensorKind::Fan,
+        SensorKind::Voltage,
+        SensorKind::Current,
+        SensorKind::Power,
+    ];
+
+    /// Returns the prefix of the hwmon attribute files of the kind, such as `temp` in `temp1_input`
+    fn prefix(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "temp",
+            SensorKind::Fan => "fan",
+            SensorKind::Voltage => "in",
+            SensorKind::Current => "curr",
+            SensorKind::Power => "power",
+        }
+    }
+
+    /// Returns what the raw sysfs values are divided by to get the unit of the kind
+    fn divisor(&self) -> f32 {
+        match self {
+            // millidegrees, RPM, millivolts, milliamperes and microwatts
+            SensorKind::Temperature => 1000_f32,
+            SensorKind::Fan => 1_f32,
+            SensorKind::Voltage => 1000_f32,
+            SensorKind::Current => 1000_f32,
+            SensorKind::Power => 1000000_f32,
+        }
+    }
+
+    /// Returns the unit of the readings of the kind
+    pub fn unit(&self) -> &'static str {
+        match self {
+            SensorKind::Temperature => "°C",
+            SensorKind::Fan => "RPM",
+            SensorKind::Voltage => "V",
+            SensorKind::Current => "A",
+            SensorKind::Power => "W",
+        }
+    }
+}
+
+/// How close the reading of a sensor is to its limits
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SensorStatus {
+    Normal,
+    /// Past the high limit or within 10% of the critical one
+    High,
+    /// Past the critical limit, or outside the min and max of fans and voltages
+    Critical,
+}
+
+/// Contains a single reading of a hardware sensor
+/// ### Fields
+/// * `chip` - The name of the hwmon driver or the type of the thermal zone
+/// * `device` - The sysfs device of the chip, such as `hwmon3` or `thermal_zone0`, it tells
+///   apart chips with the same name like the two `coretemp` of a dual socket system
+/// * `label` - The label of the sensor, or its attribute name such as `temp1` without a label
+/// * `kind` - What the sensor measures
+/// * `value` - The reading in the unit of the kind
+/// * `min` - The low limit of the sensor
+/// * `max` - The high limit of the sensor
+/// * `crit` - The critical limit of the sensor
+pub struct Sensor {
+    pub chip: String,
+    pub device: String,
+    pub label: String,
+    pub kind: SensorKind,
+    pub value: f32,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub crit: Option<f32>,
+}
+
+impl Sensor {
+    /// Checks how close the reading is to the limits of the sensor
+    pub fn status(&self) -> SensorStatus {
+        if let Some(crit) = self.crit {
+            if self.value >= crit {
+                return SensorStatus::Critical;
+            }
+            if self.value >= crit - crit.abs() * 0.1 {
+                return SensorStatus::High;
+            }
+        }
+        let below_min = self.min.is_some_and(|min| self.value < min);
+        let above_max = self.max.is_some_and(|max| self.value >= max);
+        match self.kind {
+            // Stopped fans and voltages out of range are failures rather than warnings
+            SensorKind::Fan | SensorKind::Voltage | SensorKind::Current if below_min || above_max => SensorStatus::Critical,
+            _ if above_max => SensorStatus::High,
+            _ => SensorStatus::Normal,
+        }
+    }
+
+    /// Checks if the sensor measures the temperature of the CPU
+    pub fn is_cpu_temperature(&self) -> bool {
+        self.kind == SensorKind::Temperature && CPU_CHIPS.contains(&self.chip.as_str())
+    }
+}
+
+/// Reads every hwmon sensor and the thermal zones that hwmon doesn't already cover,
+/// returns no sensors if the directories don't exist like on most virtual machines
+/// ### Parameters
+/// * `class_root` - The sysfs class directory, `SYS_CLASS` outside of tests
+pub fn read_sensors(class_root: &Path) -> Vec<Sensor> {
+    let mut sensors: Vec<Sensor> = Vec::new();
+    let mut chips: HashSet<String> = HashSet::new();
+    for device in numbered_entries(&class_root.join("hwmon"), "hwmon") {
+        // Older drivers keep their attributes in the device directory
+        let directory = if device.join("name").exists() { device.clone() } else { device.join("device") };
+        let chip = match read_string(&directory.join("name")) {
+            Some(chip) => chip,
+            None => continue,
+        };
+        read_hwmon_chip(&directory, &chip, &device_name(&device), &mut sensors);
+        chips.insert(chip);
+    }
+    for zone in numbered_entries(&class_root.join("thermal"), "thermal_zone") {
+        let chip = match read_string(&zone.join("type")) {
+            Some(chip) => chip,
+            None => continue,
+        };
+        // Most thermal zones are also registered as hwmon devices with the same name
+        if chips.contains(&chip) {
+            continue;
+        }
+        if let Some(sensor) = read_thermal_zone(&zone, &chip, &device_name(&zone)) {
+            sensors.push(sensor);
+        }
+    }
+    sensors
+}
+
+/// Reads the sensors of a single hwmon chip, ordered by kind and index
+/// ### Parameters
+/// * `directory` - The directory with the attribute files of the chip
+/// * `chip` - The name of the chip
+/// * `device` - The name of the hwmon device of the chip
+/// * `sensors` - The sensors to add to
+fn read_hwmon_chip(directory: &Path, chip: &str, device: &str, sensors: &mut Vec<Sensor>) {
+    let names: Vec<String> = match fs::read_dir(directory) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.file_name().to_string_lossy().into_owned()).collect(),
+        Err(_) => return,
+    };
+    for kind in SensorKind::ALL.iter() {
+        let mut indexes: Vec<u32> = names
+            .iter()
+            .filter_map(|name| name.strip_prefix(kind.prefix()))
+            .filter_map(|rest| rest.strip_suffix("_input").or_else(|| rest.strip_suffix("_average")))
+            .filter_map(|index| index.parse().ok())
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+        for index in indexes {
+            let attribute = format!("{}{}", kind.prefix(), index);
+            let value = read_value::<f32>(&directory.join(format!("{}_input", attribute)))
+                .or_else(|| read_value(&directory.join(format!("{}_average", attribute))));
+            // Sensors that exist but can't be read at the moment are left out
+            let value = match value {
+                Some(value) => value / kind.divisor(),
+                None => continue,
+            };
+            let limit = |suffix: &str| read_value::<f32>(&directory.join(format!("{}_{}", attribute, suffix))).map(|limit| limit / kind.divisor());
+            sensors.push(Sensor {
+                chip: chip.to_string(),
+                device: device.to_string(),
+                label: read_string(&directory.join(format!("{}_label", attribute))).unwrap_or_else(|| attribute.clone()),
+                kind: *kind,
+                value,
+                min: limit("min"),
+                max: limit("max").or_else(|| limit("cap")),
+                crit: limit("crit"),
+            });
+        }
+    }
+}
+
+/// Reads the temperature of a thermal zone, its critical and hot trip points are the limits
+/// ### Parameters
+/// * `zone` - The directory of the thermal zone
+/// * `chip` - The type of the thermal zone
+/// * `device` - The name of the thermal zone directory
+fn read_thermal_zone(zone: &Path, chip: &str, device: &str) -> Option<Sensor> {
+    let value = read_value::<f32>(&zone.join("temp"))? / 1000_f32;
+    let mut max: Option<f32> = None;
+    let mut crit: Option<f32> = None;
+    let mut trip = 0;
+    while let Some(trip_type) = read_string(&zone.join(format!("trip_point_{}_type", trip))) {
+        let temp = read_value::<f32>(&zone.join(format!("trip_point_{}_temp", trip))).map(|temp| temp / 1000_f32);
+        match trip_type.as_str() {
+            "critical" => crit = crit.or(temp),
+            "hot" => max = max.or(temp),
+            _ => {},
+        }
+        trip += 1;
+    }
+    Some(Sensor {
+        chip: chip.to_string(),
+        device: device.to_string(),
+        label: chip.to_string(),
+        kind: SensorKind::Temperature,
+        value,
+        min: None,
+        max,
+        crit,
+    })
+}
+
+/// Returns the last component of a sysfs device path, such as `hwmon3`
+/// ### Parameters
+/// * `path` - The path of the device
+fn device_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Lists the entries of the directory named like `hwmon0`, `hwmon1` in numeric order
+/// ### Parameters
+/// * `directory` - The directory to list
+/// * `prefix` - The name of the entries before the number
+fn numbered_entries(directory: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<(u32, PathBuf)> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let number = entry.file_name().to_str()?.strip_prefix(prefix)?.parse().ok()?;
+                Some((number, entry.path()))
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    entries.sort_by_key(|(number, _)| *number);
+    entries.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::FakeSysfs;
+
+    #[test]
+    fn missing_sysfs_has_no_sensors() {
+        let sysfs = FakeSysfs::new("sensors-missing");
+        assert!(read_sensors(&sysfs.root).is_empty());
+        assert!(read_sensors(&sysfs.root.join("does-not-exist")).is_empty());
+    }
+
+    #[test]
+    fn reads_hwmon_sensors_with_labels_and_limits() {
+        let sysfs = FakeSysfs::new("sensors-hwmon");
+        sysfs.write("hwmon/hwmon0/name", "coretemp");
+        sysfs.write("hwmon/hwmon0/temp1_input", "45000");
+        sysfs.write("hwmon/hwmon0/temp1_label", "Package id 0");
+        sysfs.write("hwmon/hwmon0/temp1_max", "80000");
+        sysfs.write("hwmon/hwmon0/temp1_crit", "100000");
+        sysfs.write("hwmon/hwmon0/temp2_input", "43000");
+        sysfs.write("hwmon/hwmon1/name", "nct6775");
+        sysfs.write("hwmon/hwmon1/fan1_input", "1200");
+        sysfs.write("hwmon/hwmon1/fan1_min", "300");
+        sysfs.write("hwmon/hwmon1/in0_input", "1040");
+        sysfs.write("hwmon/hwmon1/in0_label", "Vcore");
+        // Older drivers keep the attributes in the device directory
+        sysfs.write("hwmon/hwmon2/device/name", "amdgpu");
+        sysfs.write("hwmon/hwmon2/device/power1_average", "15500000");
+        sysfs.write("hwmon/hwmon2/device/power1_cap", "200000000");
+
+        let sensors = read_sensors(&sysfs.root);
+        assert_eq!(sensors.len(), 5);
+        assert_eq!(sensors[0].chip, "coretemp");
+        assert_eq!(sensors[0].label, "Package id 0");
+        assert_eq!(sensors[0].value, 45_f32);
+        assert_eq!(sensors[0].max, Some(80_f32));
+        assert_eq!(sensors[0].crit, Some(100_f32));
+        assert_eq!(sensors[1].label, "temp2");
+        assert_eq!(sensors[1].crit, None);
+        assert_eq!(sensors[2].kind, SensorKind::Fan);
+        assert_eq!(sensors[2].value, 1200_f32);
+        assert_eq!(sensors[2].min, Some(300_f32));
+        assert_eq!(sensors[3].kind, SensorKind::Voltage);
+        assert_eq!(sensors[3].label, "Vcore");
+        assert_eq!(sensors[3].value, 1.04_f32);
+        assert_eq!(sensors[4].chip, "amdgpu");
+        assert_eq!(sensors[4].kind, SensorKind::Power);
+        assert_eq!(sensors[4].value, 15.5_f32);
+        assert_eq!(sensors[4].max, Some(200_f32));
+    }
+
+    #[test]
+    fn orders_hwmon_devices_numerically() {
+        let sysfs = FakeSysfs::new("sensors-order");
+        sysfs.write("hwmon/hwmon10/name", "second");
+        sysfs.write("hwmon/hwmon10/temp1_input", "1000");
+        sysfs.write("hwmon/hwmon2/name", "first");
+        sysfs.write("hwmon/hwmon2/temp1_input", "1000");
+        let chips: Vec<String> = read_sensors(&sysfs.root).into_iter().map(|sensor| sensor.chip).collect();
+        assert_eq!(chips, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn tells_apart_chips_with_the_same_name() {
+        let sysfs = FakeSysfs::new("sensors-sockets");
+        for device in ["hwmon1", "hwmon4"].iter() {
+            sysfs.write(&format!("hwmon/{}/name", device), "coretemp");
+            sysfs.write(&format!("hwmon/{}/temp2_input", device), "45000");
+            sysfs.write(&format!("hwmon/{}/temp2_label", device), "Core 0");
+        }
+        sysfs.write("thermal/thermal_zone0/type", "x86_pkg_temp");
+        sysfs.write("thermal/thermal_zone0/temp", "47000");
+        let devices: Vec<(String, String, String)> =
+            read_sensors(&sysfs.root).into_iter().map(|sensor| (sensor.chip, sensor.device, sensor.label)).collect();
+        assert_eq!(
+            devices,
+            vec![
+                (String::from("coretemp"), String::from("hwmon1"), String::from("Core 0")),
+                (String::from("coretemp"), String::from("hwmon4"), String::from("Core 0")),
+                (String::from("x86_pkg_temp"), String::from("thermal_zone0"), String::from("x86_pkg_temp")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_thermal_zones_not_covered_by_hwmon() {
+        let sysfs = FakeSysfs::new("sensors-thermal");
+        sysfs.write("hwmon/hwmon0/name", "acpitz");
+        sysfs.write("hwmon/hwmon0/temp1_input", "30000");
+        sysfs.write("thermal/thermal_zone0/type", "acpitz");
+        sysfs.write("thermal/thermal_zone0/temp", "30000");
+        sysfs.write("thermal/thermal_zone1/type", "cpu_thermal");
+        sysfs.write("thermal/thermal_zone1/temp", "61500");
+        sysfs.write("thermal/thermal_zone1/trip_point_0_type", "passive");
+        sysfs.write("thermal/thermal_zone1/trip_point_0_temp", "70000");
+        sysfs.write("thermal/thermal_zone1/trip_point_1_type", "hot");
+        sysfs.write("thermal/thermal_zone1/trip_point_1_temp", "80000");
+        sysfs.write("thermal/thermal_zone1/trip_point_2_type", "critical");
+        sysfs.write("thermal/thermal_zone1/trip_point_2_temp", "90000");
+
+        let sensors = read_sensors(&sysfs.root);
+        assert_eq!(sensors.len(), 2);
+        assert_eq!(sensors[1].chip, "cpu_thermal");
+        assert_eq!(sensors[1].value, 61.5_f32);
+        assert_eq!(sensors[1].max, Some(80_f32));
+        assert_eq!(sensors[1].crit, Some(90_f32));
+        assert!(sensors[1].is_cpu_temperature());
+        assert!(!sensors[0].is_cpu_temperature());
+    }
+
+    #[test]
+    fn skips_unreadable_sensors() {
+        let sysfs = FakeSysfs::new("sensors-unreadable");
+        sysfs.write("hwmon/hwmon0/name", "nvme");
+        sysfs.write("hwmon/hwmon0/temp1_input", "");
+        sysfs.write("hwmon/hwmon0/temp2_input", "38850");
+        sysfs.write("hwmon/hwmon1/temp1_input", "50000");
+        let sensors = read_sensors(&sysfs.root);
+        assert_eq!(sensors.len(), 1);
+        assert_eq!(sensors[0].label, "temp2");
+    }
+
+    fn sensor(kind: SensorKind, value: f32, min: Option<f32>, max: Option<f32>, crit: Option<f32>) -> Sensor {
+        Sensor {
+            chip: String::from("test"),
+            device: String::from("hwmon0"),
+            label: String::from("test"),
+            kind,
+            value,
+            min,
+            max,
+            crit,
+        }
+    }
+
+    #[test]
+    fn status_depends_on_the_limits() {
+        let temperature = |value| sensor(SensorKind::Temperature, value, None, Some(80_f32), Some(100_f32)).status();
+        assert_eq!(temperature(50_f32), SensorStatus::Normal);
+        assert_eq!(temperature(85_f32), SensorStatus::High);
+        assert_eq!(temperature(95_f32), SensorStatus::High);
+        assert_eq!(temperature(100_f32), SensorStatus::Critical);
+        assert_eq!(sensor(SensorKind::Temperature, 50_f32, None, None, None).status(), SensorStatus::Normal);
+        assert_eq!(sensor(SensorKind::Fan, 0_f32, Some(300_f32), None, None).status(), SensorStatus::Critical);
+        assert_eq!(sensor(SensorKind::Fan, 900_f32, Some(300_f32), None, None).status(), SensorStatus::Normal);
+        assert_eq!(sensor(SensorKind::Voltage, 1.5_f32, Some(0.8_f32), Some(1.4_f32), None).status(), SensorStatus::Critical);
+        assert_eq!(sensor(SensorKind::Power, 210_f32, None, Some(200_f32), None).status(), SensorStatus::High);
+    }
+}
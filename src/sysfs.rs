@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Reads a sysfs file with a single line of text
+/// ### Parameters
+/// * `path` - The path of the file
+pub fn read_string(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let content = content.trim();
+    if content.is_empty() {
+        return None;
+    }
+    Some(content.to_string())
+}
+
+/// Reads a sysfs file with a single number
+/// ### Parameters
+/// * `path` - The path of the file
+pub fn read_value<T: FromStr>(path: &Path) -> Option<T> {
+    read_string(path)?.parse().ok()
+}
+
+/// A fake sysfs directory for the tests of the collectors, removed when dropped
+/// ### Fields
+/// * `root` - The directory standing in for the sysfs one
+#[cfg(test)]
+pub struct FakeSysfs {
+    pub root: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl FakeSysfs {
+    /// Creates an empty directory, the name keeps the tests running in parallel apart
+    /// ### Parameters
+    /// * `name` - The name of the test
+    pub fn new(name: &str) -> FakeSysfs {
+        let root = std::env::temp_dir().join(format!("rctop-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        FakeSysfs { root }
+    }
+
+    /// Writes a file with a single line, creating its directories
+    /// ### Parameters
+    /// * `path` - The path of the file relative to the root
+    /// * `content` - The line to write
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("{}\n", content)).unwrap();
+    }
+}
+
+#[cfg(test)]
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_trimmed_lines_and_numbers() {
+        let sysfs = FakeSysfs::new("sysfs-read");
+        sysfs.write("name", "  coretemp ");
+        sysfs.write("temp", "42000");
+        sysfs.write("empty", "");
+        assert_eq!(read_string(&sysfs.root.join("name")).as_deref(), Some("coretemp"));
+        assert_eq!(read_value::<f32>(&sysfs.root.join("temp")), Some(42000_f32));
+        assert_eq!(read_value::<u32>(&sysfs.root.join("name")), None);
+        assert_eq!(read_string(&sysfs.root.join("empty")), None);
+        assert_eq!(read_string(&sysfs.root.join("missing")), None);
+    }
+}
@@ -1,28 +1,277 @@
-use crossterm::style::{ Color, ResetColor, SetBackgroundColor, SetForegroundColor };
+use crossterm::style::{ Color, ResetColor };
 use crossterm::terminal::{
     Clear,
-    ClearType::{All, CurrentLine},
+    ClearType::{All, CurrentLine, FromCursorDown, UntilNewLine},
     ScrollUp,
+    enable_raw_mode,
+    disable_raw_mode,
 };
 use crossterm::{
     cursor::{MoveTo, Hide, Show},
     execute,
-    event::{Event, read, KeyCode},
 };
 
 use std::{
-  process,
+  cmp::Ordering,
+  collections::HashSet,
+  error::Error,
   io::{stdout},
+  time::{SystemTime, UNIX_EPOCH},
 };
-use std::sync::{Arc, Mutex};
 
-use crate::datafetcher::SystemData;
+use crate::color::{SetBackgroundColor, SetForegroundColor};
+use crate::control;
+use crate::datafetcher::{CPULoad, DiskData, DiskIOData, NetworkData, PowerData, ProcessDetail, ProcessInfo, RAMData, SensorData, SystemData, ThreadData, ThreadInfo};
+use crate::filter::ProcessFilter;
+use crate::history::{self, History};
+use crate::metrics;
+use crate::proctree;
+use crate::sensors::{SensorKind, SensorStatus};
 use crate::utils;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The characters of the sparklines from the lowest to the highest value
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The names of the tabs in the menu header, the index is the first value of the selection
+pub const TABS: [&str; 7] = ["Overview", "Processes", "Details", "Threads", "Sensors", "CPU", "Graphs"];
+pub const TAB_OVERVIEW: usize = 0;
+pub const TAB_PROCESSES: usize = 1;
+pub const TAB_DETAILS: usize = 2;
+pub const TAB_THREADS: usize = 3;
+pub const TAB_SENSORS: usize = 4;
+pub const TAB_CPU: usize = 5;
+pub const TAB_GRAPHS: usize = 6;
+
+/// The columns the process table can be sorted by
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortColumn {
+  Pid,
+  User,
+  State,
+  Nice,
+  Cpu,
+  Memory,
+  Virtual,
+  Threads,
+  Start,
+  Command,
+}
+
+impl SortColumn {
+  pub const ALL: [SortColumn; 10] = [
+    SortColumn::Pid,
+    SortColumn::User,
+    SortColumn::State,
+    SortColumn::Nice,
+    SortColumn::Cpu,
+    SortColumn::Memory,
+    SortColumn::Virtual,
+    SortColumn::Threads,
+    SortColumn::Start,
+    SortColumn::Command,
+  ];
+
+  /// Returns the column header of the sort column
+  pub fn name(&self) -> &'static str {
+    match self {
+      SortColumn::Pid => "PID",
+      SortColumn::User => "USER",
+      SortColumn::State => "S",
+      SortColumn::Nice => "NI",
+      SortColumn::Cpu => "CPU%",
+      SortColumn::Memory => "RSS",
+      SortColumn::Virtual => "VIRT",
+      SortColumn::Threads => "THR",
+      SortColumn::Start => "START",
+      SortColumn::Command => "COMMAND",
+    }
+  }
+
+  /// Finds the column by its header or a short name such as `cpu` or `mem`, case insensitive
+  /// ### Parameters
+  /// * `name` - The name of the column
+  pub fn from_name(name: &str) -> Option<SortColumn> {
+    let name = name.to_lowercase();
+    let alias = match name.as_str() {
+      "state" => Some(SortColumn::State),
+      "nice" => Some(SortColumn::Nice),
+      "cpu" => Some(SortColumn::Cpu),
+      "mem" | "memory" => Some(SortColumn::Memory),
+      "virtual" => Some(SortColumn::Virtual),
+      "threads" => Some(SortColumn::Threads),
+      "time" => Some(SortColumn::Start),
+      _ => None,
+    };
+    alias.or_else(|| SortColumn::ALL.iter().find(|column| column.name().to_lowercase() == name).cloned())
+  }
+
+  /// Checks if the column is sorted in descending order when it is chosen, numbers are
+  /// most interesting from the largest, text from the start of the alphabet
+  pub fn default_descending(&self) -> bool {
+    matches!(self, SortColumn::Cpu | SortColumn::Memory | SortColumn::Virtual | SortColumn::Threads)
+  }
+
+  /// Returns the column after this one, wrapping around at the end
+  pub fn next(&self) -> SortColumn {
+    let index = SortColumn::ALL.iter().position(|column| column == self).unwrap_or(0);
+    SortColumn::ALL[(index + 1) % SortColumn::ALL.len()]
+  }
+
+  /// Returns the column before this one, wrapping around at the start
+  pub fn previous(&self) -> SortColumn {
+    let index = SortColumn::ALL.iter().position(|column| column == self).unwrap_or(0);
+    SortColumn::ALL[(index + SortColumn::ALL.len() - 1) % SortColumn::ALL.len()]
+  }
+}
+
+/// The popups that can be opened on top of the tabs, they take all key presses while open
+pub enum Popup {
+  /// Choosing a signal to send to a process
+  /// ### Fields
+  /// * `pid` - The id of the process
+  /// * `name` - The name of the process
+  /// * `selected` - The index of the selected signal in the signal list
+  /// * `number` - The signal number typed by the user, used instead of the list if not empty
+  /// * `confirm` - The chosen signal waiting for confirmation
+  /// * `error` - The error of the previous attempt to send a signal
+  Signal {
+    pid: u32,
+    name: String,
+    selected: usize,
+    number: String,
+    confirm: Option<i32>,
+    error: Option<String>,
+  },
+  /// Changing the nice value and the I/O priority of a process or its whole subtree
+  /// ### Fields
+  /// * `pid` - The id of the process
+  /// * `name` - The name of the process
+  /// * `field` - The index of the selected field, nice, I/O class, I/O level or scope
+  /// * `nice` - The new nice value
+  /// * `io_class` - The new I/O scheduling class, an index to `control::IO_CLASSES`
+  /// * `io_level` - The new I/O priority level
+  /// * `subtree` - Whether the descendants of the process are changed too
+  /// * `current` - The current nice value and I/O priority of the process
+  /// * `initial` - The nice value, I/O class and I/O level the popup opened with
+  /// * `error` - The error of the previous attempt to change the priorities
+  Priority {
+    pid: u32,
+    name: String,
+    field: usize,
+    nice: i32,
+    io_class: u32,
+    io_level: u32,
+    subtree: bool,
+    current: String,
+    initial: (i32, u32, u32),
+    error: Option<String>,
+  },
+}
+
+/// The kinds of text prompts shown on the bottom row
+#[derive(Clone, Copy, PartialEq)]
+pub enum PromptKind {
+  Search,
+  Filter,
+  Pid,
+}
+
+/// Contains a text prompt that takes all key presses while open
+/// ### Fields
+/// * `kind` - What the prompt is for
+/// * `input` - The text typed so far
+/// * `error` - The problem with the typed expression
+pub struct Prompt {
+  pub kind: PromptKind,
+  pub input: String,
+  pub error: Option<String>,
+}
+
+/// The ways the selection can jump between search matches, done when the table is drawn
+#[derive(Clone, Copy, PartialEq)]
+pub enum Jump {
+  /// The first match starting from the selected row
+  First,
+  /// The first match after the selected row
+  Next,
+  /// The first match before the selected row
+  Previous,
+}
+
+/// Contains the state of the UI that is shared between the keyboard and render threads
+/// ### Fields
+/// * `selection` - The selected tab and row, row 0 being the menu header
+/// * `scroll` - The index of the first process shown in the process table
+/// * `sort` - The column the process table is sorted by
+/// * `sort_descending` - Whether the process table is sorted in descending order
+/// * `show_pseudo_fs` - Whether pseudo filesystems are shown in the disk list
+/// * `tree_view` - Whether the processes are nested under their parents
+/// * `collapsed` - The ids of the processes whose children are hidden in the tree
+/// * `selected_process` - The id and name of the process on the selected row
+/// * `detail` - The id of the process shown in the details and threads tabs
+/// * `popup` - The popup that is open
+/// * `prompt` - The text prompt that is open
+/// * `search` - The search expression and its parsed form, matching processes are highlighted
+/// * `filter` - The filter expression and its parsed form, other processes are hidden
+/// * `jump` - The pending jump to a search match
+/// * `message` - The result of the latest action shown in the bottom bar until the next key press
+pub struct UIState {
+  pub selection: (usize, usize),
+  pub scroll: usize,
+  pub sort: SortColumn,
+  pub sort_descending: bool,
+  pub show_pseudo_fs: bool,
+  pub tree_view: bool,
+  pub collapsed: HashSet<u32>,
+  pub selected_process: Option<(u32, String)>,
+  pub detail: Option<u32>,
+  pub popup: Option<Popup>,
+  pub prompt: Option<Prompt>,
+  pub search: Option<(String, ProcessFilter)>,
+  pub filter: Option<(String, ProcessFilter)>,
+  pub jump: Option<Jump>,
+  pub message: Option<String>,
+}
+
+impl UIState {
+  pub fn new() -> UIState {
+    UIState {
+      selection: (TAB_OVERVIEW, 0),
+      scroll: 0,
+      sort: SortColumn::Cpu,
+      sort_descending: true,
+      show_pseudo_fs: false,
+      tree_view: false,
+      collapsed: HashSet::new(),
+      selected_process: None,
+      detail: None,
+      popup: None,
+      prompt: None,
+      search: None,
+      filter: None,
+      jump: None,
+      message: None,
+    }
+  }
+
+  /// Sorts by the given column, selecting the already active column flips the order
+  /// ### Parameters
+  /// * `column` - The column to sort by
+  pub fn sort_by(&mut self, column: SortColumn) {
+    if self.sort == column {
+      self.sort_descending = !self.sort_descending;
+    } else {
+      self.sort = column;
+      self.sort_descending = column.default_descending();
+    }
+  }
+}
+
 /// Prepare the terminal for the UI
 pub fn init() {
+  enable_raw_mode().unwrap();
   execute!(stdout(), Hide).unwrap();
 }
 
@@ -30,23 +279,49 @@ pub fn init() {
 pub fn exit() {
   println!("Exiting...");
   execute!(stdout(), Show, Clear(All), ResetColor, MoveTo(0, 0), ScrollUp(5)).unwrap();
+  disable_raw_mode().unwrap();
 }
 
+/// Clears the terminal, everything is printed again on the next redraw
 pub fn reset() -> crossterm::Result<()> {
-  execute!(stdout(), Clear(All), ResetColor, MoveTo(0, 0)).unwrap();
-  update_top_header()?;
+  execute!(stdout(), Clear(All), ResetColor, MoveTo(0, 0))?;
   Ok(())
 }
 
-pub fn update_top_header() -> crossterm::Result<()> {
-  let mut term_size = crossterm::terminal::size().unwrap();
-  let mut top_left_str: String = String::new();
-  let mut top_right_str: String = String::new();
-  top_left_str += &format!(
+/// Prints the top header with the version on the left and the load averages, task counts
+/// and uptime on the right, the load averages are coloured relative to the core count
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `term_size` - The size of the terminal
+pub fn update_top_header(sys: &SystemData, term_size: (u16, u16)) -> crossterm::Result<()> {
+  let top_left_str: String = format!(
     "RCTOP v{} [Width: {}, Height: {}]",
     VERSION, term_size.0, term_size.1
   );
-  //top_right_str += &format!("Uptime: {}", utils::parse_time(&sys.uptime));
+  // Segments of the right side with their own colours
+  let mut top_right: Vec<(String, Option<Color>)> = Vec::new();
+  if let Some(load) = sys.cpu.load_average.last() {
+    let cores = sys.cpu.count.max(1) as f32;
+    top_right.push((String::from("Load:"), None));
+    for value in [load.one, load.five, load.fifteen].iter() {
+      top_right.push((format!(" {:.2}", value), load_color(*value, cores)));
+    }
+    // The history of the one minute load as a sparkline, scaled to the core count
+    let history: Vec<f32> = sys.cpu.load_average.iter().rev().take(10).rev().map(|load| load.one).collect();
+    let scale = history.iter().cloned().fold(cores, f32::max);
+    let sparkline: String = history
+      .iter()
+      .map(|value| SPARKS[((value / scale * (SPARKS.len() - 1) as f32).round() as usize).min(SPARKS.len() - 1)])
+      .collect();
+    top_right.push((format!(" {}", sparkline), None));
+  }
+  if sys.cpu.tasks_total > 0 {
+    top_right.push((format!("  Tasks: {}/{}", sys.cpu.tasks_running, sys.cpu.tasks_total), None));
+  }
+  top_right.push((format!("  Uptime: {} ", utils::parse_time(&sys.uptime).trim_end()), None));
+
+  let right_length: usize = top_right.iter().map(|(text, _)| text.chars().count()).sum();
+  let width = term_size.0 as usize;
   execute!(
       stdout(),
       MoveTo(0, 0),
@@ -55,8 +330,18 @@ pub fn update_top_header() -> crossterm::Result<()> {
       SetForegroundColor(Color::Black),
       MoveTo(0, 0)
   )?;
-  print!("{}", top_left_str);
-  print!("{}", top_right_str);
+  if top_left_str.len() + right_length < width {
+    print!("{:<width$}", top_left_str, width = width - right_length);
+    for (text, color) in top_right {
+      if let Some(color) = color {
+        execute!(stdout(), SetBackgroundColor(color), SetForegroundColor(Color::White))?;
+      }
+      print!("{}", text);
+      execute!(stdout(), SetBackgroundColor(Color::DarkCyan), SetForegroundColor(Color::Black))?;
+    }
+  } else {
+    print!("{:<width$}", truncate_end(&top_left_str, width), width = width);
+  }
   execute!(
       stdout(),
       ResetColor,
@@ -64,9 +349,1487 @@ pub fn update_top_header() -> crossterm::Result<()> {
   Ok(())
 }
 
-pub fn update_menu_header(selection: &mut (usize, usize), term_size: (u16, u16)) {
+/// Returns the background colour of a load average, none while there is spare capacity,
+/// yellow when the cores are mostly busy and red when tasks have to wait for a core
+/// ### Parameters
+/// * `load` - The load average
+/// * `cores` - The CPU core count
+fn load_color(load: f32, cores: f32) -> Option<Color> {
+  if load >= cores {
+    Some(Color::DarkRed)
+  } else if load >= cores * 0.7 {
+    Some(Color::DarkYellow)
+  } else {
+    None
+  }
+}
+
+/// Prints the tabs on the second row, the selected tab is highlighted and
+/// underlined while the menu has the focus
+/// ### Parameters
+/// * `selection` - The selected tab and row
+/// * `term_size` - The size of the terminal
+pub fn update_menu_header(selection: &(usize, usize), term_size: (u16, u16)) -> crossterm::Result<()> {
+  execute!(stdout(), MoveTo(0, 1), Clear(CurrentLine))?;
+  let mut width: usize = 0;
+  for (i, tab) in TABS.iter().enumerate() {
+    let label = format!(" {} ", tab);
+    width += label.len();
+    if width > term_size.0 as usize {
+      break;
+    }
+    if i == selection.0 {
+      execute!(stdout(), SetBackgroundColor(Color::DarkCyan), SetForegroundColor(Color::Black))?;
+    }
+    if i == selection.0 && selection.1 == 0 {
+      print!("[{}]", tab);
+    } else {
+      print!("{}", label);
+    }
+    execute!(stdout(), ResetColor)?;
+  }
+  Ok(())
+}
+
+/// Prints the contents of the selected tab between the menu header and the bottom bar
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `state` - The state of the UI, the selection and scroll are clamped to the content
+/// * `term_size` - The size of the terminal
+pub fn print_system_data(sys: &SystemData, state: &mut UIState, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  match state.selection.0 {
+    TAB_PROCESSES => print_process_table(sys, state, term_size)?,
+    TAB_DETAILS => print_details(sys, state, term_size)?,
+    TAB_THREADS => print_thread_table(sys, state, term_size)?,
+    TAB_SENSORS => {
+      // The panel has no selectable rows
+      state.selection.1 = 0;
+      let mut row = print_sensor_panel(&sys.sensors, 2, term_size)?;
+      if sys.power.count > 0 && row + 1 < term_size.1.saturating_sub(1) {
+        execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+        row = print_power_panel(&sys.power, row + 1, term_size)?;
+      }
+      execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+    },
+    TAB_CPU => {
+      // The panel has no selectable rows
+      state.selection.1 = 0;
+      let row = print_cpu_panel(sys, 2, term_size)?;
+      execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+    },
+    TAB_GRAPHS => {
+      // The panel has no selectable rows
+      state.selection.1 = 0;
+      let row = print_graphs_panel(sys, 2, term_size)?;
+      execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+    },
+    _ => print_overview(sys, state, term_size)?,
+  }
+  if let Some(popup) = &state.popup {
+    print_popup(popup, term_size)?;
+  }
+  Ok(())
+}
+
+/// Prints the popup in a box at the center of the terminal
+/// ### Parameters
+/// * `popup` - The popup to print
+/// * `term_size` - The size of the terminal
+fn print_popup(popup: &Popup, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let mut lines: Vec<(String, Color, bool)> = Vec::new();
+  let title: String;
+  match popup {
+    Popup::Signal { pid, name, selected, number, confirm, error } => {
+      title = format!(" Send signal to {} ({}) ", name, pid);
+      match confirm {
+        Some(signal) => {
+          lines.push((format!("Send {} to {} ({})?", control::signal_name(*signal), name, pid), Color::Reset, false));
+          lines.push((String::new(), Color::Reset, false));
+          lines.push((String::from("[y] Yes   [n] No   [Esc] Cancel"), Color::DarkCyan, false));
+        }
+        None => {
+          // Rows left for the signal list after the borders, the hints and the error
+          let list_height = (term_size.1 as usize).saturating_sub(10).max(1).min(control::SIGNALS.len());
+          let start = if *selected >= list_height { selected + 1 - list_height } else { 0 };
+          for (i, (signal_name, signal)) in control::SIGNALS.iter().enumerate().skip(start).take(list_height) {
+            lines.push((format!("{:>2} {}", signal, signal_name), Color::Reset, i == *selected && number.is_empty()));
+          }
+          lines.push((String::new(), Color::Reset, false));
+          if !number.is_empty() {
+            lines.push((format!("Signal number: {}", number), Color::Reset, true));
+          }
+          lines.push((String::from("↑↓ choose, 0-9 type a number, Enter send, Esc cancel"), Color::DarkCyan, false));
+        }
+      }
+      if let Some(error) = error {
+        lines.push((error.clone(), Color::Red, false));
+      }
+    }
+    Popup::Priority { pid, name, field, nice, io_class, io_level, subtree, current, initial: _, error } => {
+      title = format!(" Priority of {} ({}) ", name, pid);
+      lines.push((format!("Current: {}", current), Color::Reset, false));
+      lines.push((String::new(), Color::Reset, false));
+      let io_class_name = control::IO_CLASSES.get(*io_class as usize).unwrap_or(&"unknown");
+      // Only the realtime and best-effort classes have levels
+      let io_level_text = if *io_class == 1 || *io_class == 2 { io_level.to_string() } else { String::from("-") };
+      let fields = [
+        format!("Nice value:  ◂ {:>3} ▸   (-20 highest, 19 lowest)", nice),
+        format!("I/O class:   ◂ {} ▸", io_class_name),
+        format!("I/O level:   ◂ {} ▸     (0 highest, 7 lowest)", io_level_text),
+        format!("Apply to:    ◂ {} ▸", if *subtree { "process and descendants" } else { "process only" }),
+      ];
+      for (i, text) in fields.iter().enumerate() {
+        lines.push((text.clone(), Color::Reset, i == *field));
+      }
+      lines.push((String::new(), Color::Reset, false));
+      lines.push((String::from("↑↓ choose, ←→ change, Enter apply, Esc cancel"), Color::DarkCyan, false));
+      if let Some(error) = error {
+        lines.push((error.clone(), Color::Red, false));
+      }
+    }
+  }
+
+  let width = lines.iter().map(|(line, _, _)| line.chars().count()).chain(Some(title.chars().count())).max().unwrap_or(0) + 4;
+  let width = width.min(term_size.0 as usize);
+  let height = lines.len() as u16 + 2;
+  let column = (term_size.0 - width as u16) / 2;
+  let mut row = term_size.1.saturating_sub(height) / 2;
+  let inner = width.saturating_sub(2);
+
+  execute!(stdout(), MoveTo(column, row), SetForegroundColor(Color::DarkCyan))?;
+  let title: String = title.chars().take(inner).collect();
+  print!("┌{}{}┐", title, "─".repeat(inner - title.chars().count()));
+  for (line, color, highlighted) in lines {
+    row += 1;
+    execute!(stdout(), MoveTo(column, row), SetForegroundColor(Color::DarkCyan))?;
+    print!("│");
+    if highlighted {
+      execute!(stdout(), SetBackgroundColor(Color::DarkCyan), SetForegroundColor(Color::Black))?;
+    } else {
+      execute!(stdout(), SetForegroundColor(color))?;
+    }
+    print!(" {:<width$}", line.chars().take(inner - 2).collect::<String>(), width = inner - 1);
+    execute!(stdout(), ResetColor, SetForegroundColor(Color::DarkCyan))?;
+    print!("│");
+  }
+  execute!(stdout(), MoveTo(column, row + 1))?;
+  print!("└{}┘", "─".repeat(inner));
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Prints the CPU cores, memory, network interfaces and disks
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `state` - The state of the UI
+/// * `term_size` - The size of the terminal
+fn print_overview(sys: &SystemData, state: &UIState, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let mut row: u16 = 2;
+  // Fetches the CPU usage for each core and prints it
+  let cpu_usages = &sys.cpu;
+  let cpu_count_string_length: usize = cpu_usages.count.to_string().len();
+  // The per core lines of /proc/stat are the online cores in order
+  let frequencies: Vec<Option<f32>> = sys.cpu_info.online().map(|core| core.frequency).collect();
+  let show_frequency = frequencies.iter().any(|frequency| frequency.is_some());
+  for i in 0..cpu_usages.count {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    print!("CPU {}:", i);
+    for _j in i.to_string().len()..cpu_count_string_length + 1 {
+      print!(" ");
+    }
+    let mut bar_width = term_size.0 - 8;
+    if show_frequency {
+      execute!(stdout(), SetForegroundColor(Color::DarkCyan))?;
+      print!("{:>8} ", frequencies.get(i).cloned().flatten().map_or(String::new(), format_frequency));
+      execute!(stdout(), ResetColor)?;
+      bar_width = bar_width.saturating_sub(9);
+    }
+    print_stacked_bar(bar_width, &cpu_segments(&cpu_usages.load[i]))?;
+    row += 1;
+  }
+  // The legend of the core bar colours
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  for (name, color) in CPU_STATES.iter() {
+    execute!(stdout(), SetForegroundColor(*color))?;
+    print!("■ {}  ", name);
+  }
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  row = print_memory_panel(&sys.ram, row + 1, term_size)?;
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+
+  row = print_network_panel(&sys.network, row + 1, term_size)?;
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  row += 1;
+  // The block device panel goes next to the mount list when there is room for both
+  if term_size.0 >= 160 {
+    let disk_width = term_size.0 - 72;
+    let disk_row = print_disk_panel(&sys.disk, state.show_pseudo_fs, row, disk_width, term_size)?;
+    execute!(stdout(), MoveTo(0, disk_row), Clear(FromCursorDown))?;
+    let diskio_row = print_diskio_panel(&sys.diskio, disk_width + 2, row, term_size.0 - disk_width - 2, term_size)?;
+    row = disk_row.max(diskio_row);
+  } else {
+    row = print_disk_panel(&sys.disk, state.show_pseudo_fs, row, term_size.0, term_size)?;
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    row = print_diskio_panel(&sys.diskio, 0, row + 1, term_size.0, term_size)?;
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+  Ok(())
+}
+
+/// Prints the CPU model, topology and caches followed by a table of the cores with their
+/// topology, frequencies and usage starting from the given row, stops before the bottom bar
+/// and returns the first row after the panel
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_cpu_panel(sys: &SystemData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  let last_row = term_size.1.saturating_sub(1);
+  let width = term_size.0 as usize;
+  let info = &sys.cpu_info;
+  let online = info.online().count();
+  let plural = |count: usize, name: &str| format!("{} {}{}", count, name, if count == 1 { "" } else { "s" });
+  let mut lines: Vec<(&str, String)> = Vec::new();
+  lines.push(("Model", info.model.clone().unwrap_or_else(|| String::from("unknown"))));
+  if let Some(vendor) = &info.vendor {
+    lines.push(("Vendor", vendor.clone()));
+  }
+  let mut topology = format!(
+    "{}, {}, {}",
+    plural(info.sockets, "socket"),
+    plural(info.physical_cores, "core"),
+    plural(online, "thread"),
+  );
+  if info.physical_cores > 0 && online > info.physical_cores {
+    topology += &format!(" ({} per core)", online / info.physical_cores);
+  }
+  if info.cores.len() > online {
+    topology += &format!(", {} offline", info.cores.len() - online);
+  }
+  lines.push(("Topology", topology));
+  lines.push(("NUMA", if info.nodes == 0 { String::from("none") } else { plural(info.nodes, "node") }));
+  if let Some(driver) = &info.driver {
+    lines.push(("Scaling", driver.clone()));
+  }
+  if !info.caches.is_empty() {
+    let caches: Vec<String> = info
+      .caches
+      .iter()
+      .map(|cache| {
+        let mut text = format!("{} {} × {}", cache.name(), utils::parse_size(&cache.size), cache.instances);
+        if cache.shared_by > 1 {
+          text += &format!(" (per {} threads)", cache.shared_by);
+        }
+        text
+      })
+      .collect();
+    lines.push(("Caches", caches.join("   ")));
+  }
+  for (name, value) in lines {
+    if row >= last_row {
+      return Ok(row);
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+    print!("{:<10}", name);
+    execute!(stdout(), ResetColor)?;
+    print!("{}", truncate_end(&value, width.saturating_sub(10)));
+    row += 1;
+  }
+  if row + 1 >= last_row {
+    return Ok(row);
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  row += 1;
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetBackgroundColor(Color::DarkGreen), SetForegroundColor(Color::Black))?;
+  let header = format!(
+    "{:>4} {:>6} {:>5} {:>5} {:<10} {:>9} {:>9} {:>9} {:<13} {:>6}",
+    "CPU", "Socket", "Core", "Node", "Siblings", "Frequency", "Min", "Max", "Governor", "Usage"
+  );
+  print!("{:<width$}", truncate_end(&header, width), width = width);
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+  let optional = |value: Option<u32>| value.map_or(String::from("-"), |value| value.to_string());
+  let frequency = |value: Option<f32>| value.map_or(String::from("-"), format_frequency);
+  let mut online_index: usize = 0;
+  for core in &info.cores {
+    if row >= last_row {
+      break;
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    if !core.online {
+      execute!(stdout(), SetForegroundColor(Color::DarkGrey))?;
+      print!("{}", truncate_end(&format!("{:>4} offline", core.id), width));
+      execute!(stdout(), ResetColor)?;
+      row += 1;
+      continue;
+    }
+    let siblings: Vec<String> = core.siblings.iter().map(|sibling| sibling.to_string()).collect();
+    let usage = sys.cpu.usage.get(online_index).cloned();
+    online_index += 1;
+    let line = format!(
+      "{:>4} {:>6} {:>5} {:>5} {:<10} {:>9} {:>9} {:>9} {:<13} {:>6}",
+      core.id,
+      optional(core.socket),
+      optional(core.core),
+      optional(core.node),
+      truncate_end(&siblings.join(","), 10),
+      frequency(core.frequency),
+      frequency(core.min_frequency),
+      frequency(core.max_frequency),
+      truncate_end(core.governor.as_deref().unwrap_or("-"), 13),
+      usage.map_or(String::from("-"), |usage| format!("{:.1}%", usage)),
+    );
+    print!("{}", truncate_end(&line, width));
+    // The usage bar fills the rest of wide terminals
+    let line_width = line.chars().count() as u16 + 2;
+    if let (Some(load), true) = (sys.cpu.load.get(online_index - 1), term_size.0 > line_width + 10) {
+      print!("  ");
+      print_stacked_bar(term_size.0 - line_width, &cpu_segments(load))?;
+    }
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Prints the graphs of the total CPU usage, the memory and swap usage, the network
+/// throughput and the usage of every core, sized to fill the rows down to the bottom bar,
+/// the cores are left out when there is no room for them, returns the first row after the panel
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_graphs_panel(sys: &SystemData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let history = &sys.history;
+  let width = term_size.0;
+  let available = term_size.1.saturating_sub(1).saturating_sub(row);
+  // Every graph has a title row above it
+  let core_columns = (width / 30).max(1);
+  let mut core_rows = (history.cores.len() as u16).div_ceil(core_columns);
+  if available < core_rows * 3 + 9 {
+    core_rows = 0;
+  }
+  let height = (available - core_rows * 3) / 3;
+  if height < 2 {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+    print!("{}", truncate_end("The terminal is too small for the graphs", width as usize));
+    execute!(stdout(), ResetColor)?;
+    return Ok(row + 1);
+  }
+  let half = width / 2;
+  let mut row = row;
+
+  let title = format!("CPU {:.1}%", history.cpu.latest().unwrap_or(0_f32));
+  print_graph(GraphArea { column: 0, row, width, height }, &title, &history.cpu, 100_f32, Color::DarkGreen)?;
+  row += height;
+
+  let memory_title = format!("Memory {:.1}%", history.memory.latest().unwrap_or(0_f32));
+  if sys.ram.swap_total > 0 {
+    print_graph(GraphArea { column: 0, row, width: half - 1, height }, &memory_title, &history.memory, 100_f32, Color::DarkYellow)?;
+    let swap_title = format!("Swap {:.1}%", history.swap.latest().unwrap_or(0_f32));
+    print_graph(GraphArea { column: half + 1, row, width: width - half - 1, height }, &swap_title, &history.swap, 100_f32, Color::DarkRed)?;
+  } else {
+    print_graph(GraphArea { column: 0, row, width, height }, &memory_title, &history.memory, 100_f32, Color::DarkYellow)?;
+  }
+  row += height;
+
+  // Both directions share the scale of the busier one over the visible samples
+  let samples = (half as usize).saturating_sub(1) * 2;
+  let network_max = history.network_rx.max(samples).max(history.network_tx.max(samples)).max(1024_f32);
+  let rx_title = format!("RX {}/s", utils::parse_size(&(history.network_rx.latest().unwrap_or(0_f32) as u64)));
+  let tx_title = format!("TX {}/s", utils::parse_size(&(history.network_tx.latest().unwrap_or(0_f32) as u64)));
+  print_graph(GraphArea { column: 0, row, width: half - 1, height }, &rx_title, &history.network_rx, network_max, Color::DarkCyan)?;
+  print_graph(GraphArea { column: half + 1, row, width: width - half - 1, height }, &tx_title, &history.network_tx, network_max, Color::DarkMagenta)?;
+  row += height;
+
+  let core_width = width / core_columns;
+  for (i, core) in history.cores.iter().enumerate().take((core_rows * core_columns) as usize) {
+    let column = (i as u16 % core_columns) * core_width;
+    let area = GraphArea { column, row: row + (i as u16 / core_columns) * 3, width: core_width - 1, height: 3 };
+    let title = format!("CPU {} {:.1}%", i, core.latest().unwrap_or(0_f32));
+    print_graph(area, &title, core, 100_f32, Color::DarkGreen)?;
+  }
+  Ok(row + core_rows * 3)
+}
+
+/// Contains the part of the screen a graph is drawn in
+/// ### Fields
+/// * `column` - The column where the graph starts
+/// * `row` - The row of the title
+/// * `width` - The width of the graph
+/// * `height` - The height of the graph including the title
+struct GraphArea {
+  column: u16,
+  row: u16,
+  width: u16,
+  height: u16,
+}
+
+/// Prints a titled graph of the history, the title takes the first row and the graph
+/// the rest, the rows are cleared when the graph starts from the first column
+/// ### Parameters
+/// * `area` - The part of the screen to draw in
+/// * `title` - The title of the graph
+/// * `history` - The samples to draw
+/// * `max` - The value of a full column
+/// * `color` - The color of the graph
+fn print_graph(area: GraphArea, title: &str, history: &History, max: f32, color: Color) -> Result<(), Box<dyn Error>> {
+  let GraphArea { column, row, width, height } = area;
+  let lines = history::braille_graph(history, width as usize, height.saturating_sub(1) as usize, max);
+  for (i, line) in Some(title.to_string()).into_iter().chain(lines).enumerate() {
+    execute!(stdout(), MoveTo(column, row + i as u16))?;
+    if column == 0 {
+      execute!(stdout(), Clear(CurrentLine))?;
+    }
+    if i == 0 {
+      execute!(stdout(), SetForegroundColor(Color::DarkCyan))?;
+      print!("{:<width$}", truncate_end(&line, width as usize), width = width as usize);
+    } else {
+      execute!(stdout(), SetForegroundColor(color))?;
+      print!("{}", line);
+    }
+  }
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Formats a frequency in MHz, the frequencies from 1 GHz up are shown in GHz
+/// ### Parameters
+/// * `frequency` - The frequency in MHz
+fn format_frequency(frequency: f32) -> String {
+  if frequency >= 1000_f32 {
+    format!("{:.2}GHz", frequency / 1000_f32)
+  } else {
+    format!("{:.0}MHz", frequency)
+  }
+}
+
+/// Prints the memory bar split into used, shared, buffer and cache memory, the swap bar
+/// and a table of the memory breakdown starting from the given row, returns the first
+/// row after the panel
+/// ### Parameters
+/// * `ram` - The memory data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_memory_panel(ram: &RAMData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  let breakdown = metrics::memory_breakdown(ram.total, ram.free, ram.available, ram.buffers, ram.shared);
+  let percentage = |value: u64| metrics::percentage(value, ram.total);
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  print!("Memory: ");
+  print_stacked_bar(
+    term_size.0 - 8,
+    &[
+      (percentage(breakdown.used), Color::DarkGreen),
+      (percentage(breakdown.shared), Color::DarkMagenta),
+      (percentage(breakdown.buffers), Color::DarkBlue),
+      (percentage(breakdown.cache), Color::DarkYellow),
+    ],
+  )?;
+  row += 1;
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+  print!("Swap:   ");
+  if ram.swap_total > 0 {
+    print_bar(
+      term_size.0 - 8,
+      metrics::percentage(ram.swap_total.saturating_sub(ram.swap_free), ram.swap_total),
+      Color::DarkRed,
+    )?;
+  } else {
+    print!("none");
+  }
+  row += 1;
+
+  let hugepages = if ram.hugepages_total > 0 {
+    format!("{}/{} × {}", ram.hugepages_total - ram.hugepages_free, ram.hugepages_total, utils::parse_size(&ram.hugepage_size))
+  } else {
+    String::from("none")
+  };
+  let cells: Vec<(&str, String, Color)> = vec![
+    ("Used", utils::parse_size(&ram.used), Color::DarkGreen),
+    ("Shared", utils::parse_size(&breakdown.shared), Color::DarkMagenta),
+    ("Buffers", utils::parse_size(&breakdown.buffers), Color::DarkBlue),
+    ("Cache", utils::parse_size(&breakdown.cache), Color::DarkYellow),
+    ("Free", utils::parse_size(&ram.free), Color::Reset),
+    ("Available", utils::parse_size(&ram.available), Color::Reset),
+    ("Slab reclaim", utils::parse_size(&ram.slab_reclaimable), Color::Reset),
+    ("Slab unreclaim", utils::parse_size(&ram.slab_unreclaimable), Color::Reset),
+    ("Dirty", utils::parse_size(&ram.dirty), Color::Reset),
+    ("Writeback", utils::parse_size(&ram.writeback), Color::Reset),
+    ("Swap used", utils::parse_size(&ram.swap_total.saturating_sub(ram.swap_free)), Color::DarkRed),
+    ("Swap cached", utils::parse_size(&ram.swap_cached), Color::Reset),
+    ("Swap total", utils::parse_size(&ram.swap_total), Color::Reset),
+    ("Huge pages", hugepages, Color::Reset),
+  ];
+  // The cells are laid out in as many columns as fit the terminal
+  let cell_width: usize = 32;
+  let columns = (term_size.0 as usize / cell_width).max(1);
+  for line in cells.chunks(columns) {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    for (label, value, color) in line {
+      execute!(stdout(), SetForegroundColor(*color))?;
+      print!("{:<15}", label);
+      execute!(stdout(), ResetColor)?;
+      print!("{:<width$}", value, width = cell_width - 15);
+    }
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// The CPU states shown in the core bars and their colours, in the order of the segments
+const CPU_STATES: [(&str, Color); 8] = [
+  ("nice", Color::DarkBlue),
+  ("user", Color::DarkGreen),
+  ("system", Color::DarkRed),
+  ("irq", Color::DarkYellow),
+  ("softirq", Color::DarkMagenta),
+  ("steal", Color::Cyan),
+  ("guest", Color::Blue),
+  ("iowait", Color::DarkGrey),
+];
+
+/// Returns the segments of a core bar in the order and colours of `CPU_STATES`
+/// ### Parameters
+/// * `load` - The time fractions of the core
+fn cpu_segments(load: &CPULoad) -> Vec<(f32, Color)> {
+  let fractions = [
+    load.nice,
+    load.user,
+    load.system,
+    load.interrupt,
+    load.softirq,
+    load.steal,
+    load.guest + load.guest_nice,
+    load.iowait,
+  ];
+  fractions.iter().zip(CPU_STATES.iter()).map(|(fraction, (_, color))| (fraction * 100_f32, *color)).collect()
+}
+
+/// Prints the bottom bar with the total CPU and memory usage on the last row
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `message` - The result of the latest action to show after the usages
+/// * `term_size` - The size of the terminal
+pub fn update_bottom_bar(sys: &SystemData, message: &Option<String>, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let bottom_row = term_size.1.saturating_sub(1);
+  let mut bottom_left_str: String = String::new();
+  let mut bottom_right_str: String = String::new();
+  bottom_left_str += &format!("CPU: {:.2}% ", sys.cpu.total);
+  // Steal time means the hypervisor is overcommitted, it is hidden from the total
+  let steal = sys.cpu.load.iter().map(|load| load.steal).sum::<f32>() / sys.cpu.count.max(1) as f32 * 100_f32;
+  if steal >= 0.1_f32 {
+    bottom_left_str += &format!("steal: {:.1}% ", steal);
+  }
+  // The hottest reading is the one that throttles the CPU
+  if let Some(temp) = sys.cpu.temp.iter().cloned().fold(None, |max: Option<f32>, temp| Some(max.map_or(temp, |max| max.max(temp)))) {
+    bottom_left_str += &format!("{:.0}°C ", temp);
+  }
+  bottom_left_str += &format!("RAM: {} / {} ", utils::parse_size(&sys.ram.used), utils::parse_size(&sys.ram.total));
+  if let Some(message) = message {
+    bottom_left_str += &format!("| {} ", message);
+  }
+  if let Some(error) = &sys.record_error {
+    bottom_left_str += &format!("| Recording failed: {} ", error);
+  }
+  if let Some(error) = &sys.push_error {
+    bottom_left_str += &format!("| Push failed: {} ", error);
+  }
+  if let Some(error) = &sys.fetch_error {
+    bottom_left_str += &format!("| Refresh failed: {} ", error);
+  }
+  for supply in sys.power.list.iter().filter(|supply| supply.system) {
+    if supply.is_battery() {
+      bottom_right_str += &format!("{}: {}", supply.name, supply.capacity.map_or(String::from("?"), |capacity| format!("{:.0}%", capacity)));
+      if let Some(status) = &supply.status {
+        bottom_right_str += &format!(" {}", status.to_lowercase());
+      }
+      if let Some(time) = supply.time_to_empty.or(supply.time_to_full) {
+        bottom_right_str += &format!(" {}", utils::parse_time(&time).trim_end());
+      }
+      bottom_right_str += " ";
+    } else if supply.online == Some(true) {
+      bottom_right_str += "AC ";
+    }
+  }
+  let bottom_right_str = bottom_right_str.trim_end().to_string();
+  execute!(
+    stdout(),
+    MoveTo(0, bottom_row),
+    Clear(CurrentLine),
+    SetBackgroundColor(Color::DarkCyan)
+  )?;
+  print!(" ");
+  // The widths are counted in characters, the temperature and battery texts aren't ASCII
+  let left_width = bottom_left_str.chars().count();
+  let right_width = bottom_right_str.chars().count();
+  if term_size.0 as usize > left_width + right_width + 2 {
+    print!("{}", bottom_left_str);
+    for _i in 0..(term_size.0 as usize - left_width - right_width - 2) {
+      print!(" ");
+    }
+    print!("{} ", bottom_right_str);
+  }
+  else if term_size.0 as usize > left_width + 1 {
+    print!("{}", bottom_left_str);
+    for _i in 0..(term_size.0 as usize - left_width - 1) {
+      print!(" ");
+    }
+  } else {
+    bottom_left_str = bottom_left_str.chars().take((term_size.0 as usize).saturating_sub(5)).collect();
+    bottom_left_str += "...";
+    print!("{} ", bottom_left_str);
+  }
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Prints the readings of the hardware sensors grouped by their chip starting from the given row,
+/// readings near their limits are yellow and past them red, stops before the bottom bar and
+/// returns the first row after the panel
+/// ### Parameters
+/// * `sensors` - The sensor data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_sensor_panel(sensors: &SensorData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  let last_row = term_size.1.saturating_sub(1);
+  let width = term_size.0 as usize;
+  if sensors.count == 0 {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+    print!("{}", truncate_end("No hardware sensors found, virtual machines usually have none", width));
+    execute!(stdout(), ResetColor)?;
+    return Ok(row + 1);
+  }
+  // Grouped by device, the chips of each socket share their name
+  let mut device: Option<&str> = None;
+  for sensor in &sensors.list {
+    if row >= last_row {
+      break;
+    }
+    if device != Some(sensor.device.as_str()) {
+      device = Some(sensor.device.as_str());
+      execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetBackgroundColor(Color::DarkGreen), SetForegroundColor(Color::Black))?;
+      print!("{:<width$}", truncate_end(&sensor.chip, width), width = width);
+      execute!(stdout(), ResetColor)?;
+      row += 1;
+      if row >= last_row {
+        break;
+      }
+    }
+    let color = match sensor.status() {
+      SensorStatus::Normal => Color::Reset,
+      SensorStatus::High => Color::Yellow,
+      SensorStatus::Critical => Color::Red,
+    };
+    // Fans read whole RPMs, the others need decimals
+    let decimals = if sensor.kind == SensorKind::Fan { 0 } else { 2 };
+    let format_value = |value: f32| format!("{:.*}{}", decimals, value, sensor.kind.unit());
+    let mut limits: String = String::new();
+    for (name, limit) in [("min", sensor.min), ("max", sensor.max), ("crit", sensor.crit)].iter() {
+      if let Some(limit) = limit {
+        limits += &format!("  {} {}", name, format_value(*limit));
+      }
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    print!("  {:<24} ", truncate_end(&sensor.label, 24));
+    execute!(stdout(), SetForegroundColor(color))?;
+    print!("{:>11}", format_value(sensor.value));
+    execute!(stdout(), ResetColor)?;
+    let limits = truncate_end(&limits, width.saturating_sub(38));
+    print!("{}", limits);
+    // Temperatures get a bar up to their critical limit, after the limits when all three
+    // of them reach past the usual column
+    let column = (38 + limits.chars().count() + 2).max(70);
+    let bar_width = width.saturating_sub(column + 2).min(50);
+    if sensor.kind == SensorKind::Temperature && width > 100 && bar_width >= 10 {
+      let limit = sensor.crit.or(sensor.max).unwrap_or(100_f32);
+      execute!(stdout(), MoveTo(column as u16, row))?;
+      print_bar(
+        bar_width as u16,
+        (sensor.value / limit * 100_f32).clamp(0_f32, 100_f32),
+        if color == Color::Reset { Color::DarkGreen } else { color },
+      )?;
+    }
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Prints the state of every battery and AC adapter starting from the given row,
+/// low batteries are red, stops before the bottom bar and returns the first row after the panel
+/// ### Parameters
+/// * `power` - The power supply data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_power_panel(power: &PowerData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  let last_row = term_size.1.saturating_sub(1);
+  let width = term_size.0 as usize;
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetBackgroundColor(Color::DarkGreen), SetForegroundColor(Color::Black))?;
+  print!("{:<width$}", truncate_end("Power supplies", width), width = width);
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+  for supply in &power.list {
+    if row >= last_row {
+      break;
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    if !supply.is_battery() {
+      let state = match supply.online {
+        Some(true) => "online",
+        Some(false) => "offline",
+        None => "unknown",
+      };
+      print!("{}", truncate_end(&format!("  {:<16} {:<8} {}", supply.name, supply.kind, state), width));
+      row += 1;
+      continue;
+    }
+    let mut details: String = String::new();
+    if let Some(status) = &supply.status {
+      details += &format!("  {}", status);
+    }
+    if let Some(power) = supply.power {
+      details += &format!("  {:.2}W", power);
+    }
+    if let Some(time) = supply.time_to_empty {
+      details += &format!("  {} to empty", utils::parse_time(&time).trim_end());
+    }
+    if let Some(time) = supply.time_to_full {
+      details += &format!("  {} to full", utils::parse_time(&time).trim_end());
+    }
+    if let Some(health) = supply.health {
+      details += &format!("  health {:.0}%", health);
+    }
+    if let Some(cycles) = supply.cycles {
+      details += &format!("  {} cycles", cycles);
+    }
+    if !supply.system {
+      details += "  (peripheral)";
+    }
+    print!("  {:<16} ", truncate_end(&supply.name, 16));
+    let capacity = supply.capacity.unwrap_or(0_f32);
+    let color = if capacity <= 10_f32 {
+      Color::Red
+    } else if capacity <= 25_f32 {
+      Color::Yellow
+    } else {
+      Color::DarkGreen
+    };
+    execute!(stdout(), SetForegroundColor(color))?;
+    print!("{:>4}", supply.capacity.map_or(String::from("?"), |capacity| format!("{:.0}%", capacity)));
+    execute!(stdout(), ResetColor)?;
+    print!("{}", truncate_end(&details, width.saturating_sub(24)));
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Prints the throughput and counters of each network interface starting from the given row,
+/// stops before the bottom bar and returns the first row after the panel
+/// ### Parameters
+/// * `network` - The network data to print
+/// * `row` - The row where the panel starts
+/// * `term_size` - The size of the terminal
+fn print_network_panel(network: &NetworkData, row: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  if row + 1 >= term_size.1 {
+    return Ok(row);
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+  print!("Network");
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+  let name_width = network.names.iter().map(|name| name.len()).max().unwrap_or(0);
+  for i in 0..network.count {
+    if row + 1 >= term_size.1 {
+      break;
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    let line = format!(
+      "{:<width$}  RX: {:>11}  TX: {:>11}  Total: {} / {}  Packets: {} / {}  Errors: {} / {}  Drops: {} / {}",
+      network.names[i],
+      format!("{}/s", utils::parse_size(&network.rx_rate[i])),
+      format!("{}/s", utils::parse_size(&network.tx_rate[i])),
+      utils::parse_size(&network.rx[i]),
+      utils::parse_size(&network.tx[i]),
+      network.rx_packets[i],
+      network.tx_packets[i],
+      network.rx_errors[i],
+      network.tx_errors[i],
+      network.rx_drops[i],
+      network.tx_drops[i],
+      width = name_width,
+    );
+    print!("{}", line.chars().take(term_size.0 as usize).collect::<String>());
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Prints a table of the mounted filesystems with usage bars starting from the given row,
+/// stops before the bottom bar and returns the first row after the panel
+/// ### Parameters
+/// * `disk` - The disk data to print
+/// * `show_pseudo` - Whether pseudo filesystems (proc, tmpfs, snaps etc.) are listed
+/// * `row` - The row where the panel starts
+/// * `width` - The width of the panel
+/// * `term_size` - The size of the terminal
+fn print_disk_panel(disk: &DiskData, show_pseudo: bool, row: u16, width: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  if row + 1 >= term_size.1 {
+    return Ok(row);
+  }
+  let visible: Vec<usize> = (0..disk.count).filter(|&i| show_pseudo || !disk.pseudo[i]).collect();
+  let device_width = visible.iter().map(|&i| disk.fs_mounted_from[i].len()).max().unwrap_or(0).clamp(6, 24);
+  let mount_width = visible.iter().map(|&i| disk.fs_mounted_on[i].len()).max().unwrap_or(0).clamp(5, 24);
+  let type_width = visible.iter().map(|&i| disk.fs_type[i].len()).max().unwrap_or(0).clamp(4, 10);
+  let header = format!(
+    "{:<dw$} {:<mw$} {:<tw$} {:>21} {:>7}  Usage ({} mounts, 'a' toggles pseudo filesystems)",
+    "Device", "Mount", "Type", "Used / Total", "Inodes", if show_pseudo { "all" } else { "real" },
+    dw = device_width, mw = mount_width, tw = type_width,
+  );
+  execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+  print!("{}", header.chars().take(width as usize).collect::<String>());
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+  for i in visible {
+    if row + 1 >= term_size.1 {
+      break;
+    }
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    let inodes = if disk.inodes_total[i] > 0 {
+      format!("{:.1}%", metrics::percentage(disk.inodes_total[i].saturating_sub(disk.inodes_free[i]), disk.inodes_total[i]))
+    } else {
+      String::from("-")
+    };
+    let line = format!(
+      "{:<dw$} {:<mw$} {:<tw$} {:>21} {:>7}  ",
+      truncate_start(&disk.fs_mounted_from[i], device_width),
+      truncate_start(&disk.fs_mounted_on[i], mount_width),
+      truncate_start(&disk.fs_type[i], type_width),
+      format!("{} / {}", utils::parse_size(&disk.used[i]), utils::parse_size(&disk.total[i])),
+      inodes,
+      dw = device_width, mw = mount_width, tw = type_width,
+    );
+    let line_width = line.chars().count() as u16;
+    if width > line_width + 10 {
+      print!("{}", line);
+      print_bar(width - line_width - 9, disk.percentage[i], Color::DarkMagenta)?;
+      print!(" {:>6.2}%", disk.percentage[i]);
+    } else {
+      print!("{}", line.chars().take(width as usize).collect::<String>());
+    }
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Prints the I/O activity of each block device at the given column starting from the given row,
+/// stops before the bottom bar and returns the first row after the panel
+/// ### Parameters
+/// * `diskio` - The block device I/O data to print
+/// * `column` - The column where the panel starts
+/// * `row` - The row where the panel starts
+/// * `width` - The width of the panel
+/// * `term_size` - The size of the terminal
+fn print_diskio_panel(diskio: &DiskIOData, column: u16, row: u16, width: u16, term_size: (u16, u16)) -> Result<u16, Box<dyn Error>> {
+  let mut row = row;
+  if row + 1 >= term_size.1 {
+    return Ok(row);
+  }
+  let name_width = diskio.names.iter().map(|name| name.len()).max().unwrap_or(0).max(6);
+  let header = format!(
+    "{:<nw$} {:>10} {:>10} {:>7} {:>7} {:>8} {:>6} {:>6}",
+    "Device", "Read/s", "Write/s", "r IOPS", "w IOPS", "Await", "Queue", "Util",
+    nw = name_width,
+  );
+  execute!(stdout(), MoveTo(column, row), Clear(UntilNewLine), SetForegroundColor(Color::DarkCyan))?;
+  print!("{}", header.chars().take(width as usize).collect::<String>());
+  execute!(stdout(), ResetColor)?;
+  row += 1;
+  for i in 0..diskio.count {
+    if row + 1 >= term_size.1 {
+      break;
+    }
+    execute!(stdout(), MoveTo(column, row), Clear(UntilNewLine))?;
+    let line = format!(
+      "{:<nw$} {:>10} {:>10} {:>7.1} {:>7.1} {:>6.2}ms {:>6.2}",
+      diskio.names[i],
+      utils::parse_size(&diskio.read_rate[i]),
+      utils::parse_size(&diskio.write_rate[i]),
+      diskio.read_iops[i],
+      diskio.write_iops[i],
+      diskio.latency[i],
+      diskio.queue_depth[i],
+      nw = name_width,
+    );
+    print!("{}", line.chars().take(width as usize).collect::<String>());
+    // Busy devices stand out so an I/O bound system is easy to spot
+    if line.chars().count() + 7 <= width as usize {
+      let color = if diskio.utilisation[i] >= 90_f32 {
+        Color::Red
+      } else if diskio.utilisation[i] >= 60_f32 {
+        Color::DarkYellow
+      } else {
+        Color::Reset
+      };
+      execute!(stdout(), SetForegroundColor(color))?;
+      print!(" {:>5.1}%", diskio.utilisation[i]);
+      execute!(stdout(), ResetColor)?;
+    }
+    row += 1;
+  }
+  Ok(row)
+}
+
+/// Sorts the processes by the given column, ties are broken by the pid so the
+/// order stays stable between refreshes
+/// ### Parameters
+/// * `processes` - The processes to sort
+/// * `column` - The column to sort by
+/// * `descending` - Whether the order is descending
+pub fn sort_processes(processes: &mut Vec<&ProcessInfo>, column: SortColumn, descending: bool) {
+  processes.sort_by(|a, b| {
+    let order = match column {
+      SortColumn::Pid => a.pid.cmp(&b.pid),
+      SortColumn::User => a.user.cmp(&b.user),
+      SortColumn::State => a.state.cmp(&b.state),
+      SortColumn::Nice => a.nice.cmp(&b.nice),
+      SortColumn::Cpu => a.cpu.partial_cmp(&b.cpu).unwrap_or(Ordering::Equal),
+      SortColumn::Memory => a.rss.cmp(&b.rss),
+      SortColumn::Virtual => a.virt.cmp(&b.virt),
+      SortColumn::Threads => a.threads.cmp(&b.threads),
+      SortColumn::Start => a.start_time.cmp(&b.start_time),
+      SortColumn::Command => a.command.cmp(&b.command),
+    };
+    let order = if descending { order.reverse() } else { order };
+    order.then(a.pid.cmp(&b.pid))
+  });
+}
+
+/// Prints the scrollable and sortable process table as a flat list or a tree,
+/// the selected row is highlighted
+/// ### Parameters
+/// * `sys` - The system data to print
+/// * `state` - The state of the UI, the selection and scroll are clamped to the process count
+/// * `term_size` - The size of the terminal
+fn print_process_table(sys: &SystemData, state: &mut UIState, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let mut processes: Vec<&ProcessInfo> = match &state.filter {
+    Some((_, filter)) => sys.processes.list.iter().filter(|process| filter.matches(process)).collect(),
+    None => sys.processes.list.iter().collect(),
+  };
+  sort_processes(&mut processes, state.sort, state.sort_descending);
+  // Siblings in the tree keep the order of the selected sort column
+  let processes = if state.tree_view {
+    proctree::build_tree(&processes, &state.collapsed)
+  } else {
+    proctree::build_list(&processes)
+  };
+
+  // Rows between the column header and the bottom bar
+  let height = (term_size.1 as usize).saturating_sub(4).max(1);
+  if let Some(jump) = state.jump.take() {
+    if let Some((_, search)) = &state.search {
+      if let Some(index) = find_match(&processes, search, state.selection.1, jump) {
+        state.selection.1 = index + 1;
+      }
+    }
+  }
+  if state.selection.1 > processes.len() {
+    state.selection.1 = processes.len();
+  }
+  state.selected_process = None;
+  if state.selection.1 > 0 {
+    let index = state.selection.1 - 1;
+    state.selected_process = Some((processes[index].process.pid, processes[index].process.name.clone()));
+    if index < state.scroll {
+      state.scroll = index;
+    } else if index >= state.scroll + height {
+      state.scroll = index + 1 - height;
+    }
+  }
+  state.scroll = state.scroll.min(processes.len().saturating_sub(height));
+
+  let mut header: String = String::new();
+  for column in SortColumn::ALL.iter() {
+    header += &match column {
+      SortColumn::Pid => format!("{:>7}", column.name()),
+      SortColumn::User => format!(" {:<9}", column.name()),
+      SortColumn::State => format!(" {}", column.name()),
+      SortColumn::Nice => format!(" {:>3}", column.name()),
+      SortColumn::Cpu => format!(" {:>6}", column.name()),
+      SortColumn::Threads => format!(" {:>4}", column.name()),
+      SortColumn::Start => format!(" {:>5}", column.name()),
+      SortColumn::Command => format!(
+        " {}{}{}",
+        column.name(),
+        if state.tree_view { " (tree)" } else { "" },
+        match &state.filter {
+          Some((expression, _)) => format!(" [filter: {}]", expression),
+          None => String::new(),
+        },
+      ),
+      _ => format!(" {:>9}", column.name()),
+    };
+    if *column == state.sort {
+      header += if state.sort_descending { "▼" } else { "▲" };
+    }
+  }
+  execute!(stdout(), MoveTo(0, 2), Clear(CurrentLine), SetBackgroundColor(Color::DarkGreen), SetForegroundColor(Color::Black))?;
+  print!("{:<width$}", header.chars().take(term_size.0 as usize).collect::<String>(), width = term_size.0 as usize);
+  execute!(stdout(), ResetColor)?;
+
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+  let mut row: u16 = 3;
+  for (index, process_row) in processes.iter().enumerate().skip(state.scroll).take(height) {
+    let process = process_row.process;
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    let marker = if process_row.collapsed {
+      "+ "
+    } else if process_row.has_children && state.tree_view {
+      "- "
+    } else {
+      ""
+    };
+    let line = format!(
+      "{:>7} {:<9} {} {:>3} {:>6.1} {:>9} {:>9} {:>4} {:>5} {}{}{}",
+      process.pid,
+      truncate_end(&process.user, 9),
+      process.state,
+      process.nice,
+      process_row.cpu,
+      utils::parse_size(&process_row.rss),
+      utils::parse_size(&process.virt),
+      process.threads,
+      utils::parse_start_time(&process.start_time, &now),
+      process_row.prefix,
+      marker,
+      process.command,
+    );
+    let line: String = line.chars().take(term_size.0 as usize).collect();
+    if state.selection.1 == index + 1 {
+      execute!(stdout(), SetBackgroundColor(Color::DarkCyan), SetForegroundColor(Color::Black))?;
+      print!("{:<width$}", line, width = term_size.0 as usize);
+      execute!(stdout(), ResetColor)?;
+    } else if state.search.as_ref().is_some_and(|(_, search)| search.matches(process)) {
+      execute!(stdout(), SetForegroundColor(Color::Yellow))?;
+      print!("{}", line);
+      execute!(stdout(), ResetColor)?;
+    } else {
+      print!("{}", line);
+    }
+    row += 1;
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+  Ok(())
+}
+
+/// Prints the details of the chosen process, the selected row scrolls the lines
+/// ### Parameters
+/// * `sys` - The system data with the details of the process
+/// * `state` - The state of the UI, the selection is clamped to the content
+/// * `term_size` - The size of the terminal
+fn print_details(sys: &SystemData, state: &mut UIState, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let width = term_size.0 as usize;
+  let mut lines: Vec<(String, Color)> = Vec::new();
+  state.selected_process = None;
+  match (state.detail, &sys.detail) {
+    (None, _) => {
+      lines.push((String::from("Press Enter on a process or d to choose a PID"), Color::DarkCyan));
+    },
+    (Some(pid), Some(detail)) if detail.pid == pid => {
+      state.selected_process = Some((detail.pid, detail.name.clone()));
+      lines = detail_lines(detail, sys.threads.as_ref().filter(|threads| threads.pid == pid), width);
+    },
+    // The fetcher reads the details of a newly chosen process on its next round
+    (Some(pid), _) if sys.processes.list.iter().any(|process| process.pid == pid) => {
+      lines.push((format!("Reading the details of process {}…", pid), Color::DarkCyan));
+    },
+    (Some(pid), _) => {
+      lines.push((format!("Process {} is not running", pid), Color::Red));
+    },
+  }
+
+  // Rows between the menu header and the bottom bar
+  let height = (term_size.1 as usize).saturating_sub(3).max(1);
+  let max_scroll = lines.len().saturating_sub(height);
+  if state.selection.1 > max_scroll + 1 {
+    state.selection.1 = max_scroll + 1;
+  }
+  let mut row: u16 = 2;
+  for (line, color) in lines.iter().skip(state.selection.1.saturating_sub(1)).take(height) {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine), SetForegroundColor(*color))?;
+    print!("{}", truncate_end(line, width));
+    execute!(stdout(), ResetColor)?;
+    row += 1;
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+  Ok(())
+}
+
+/// Turns the details of the process into the lines of the details tab
+/// ### Parameters
+/// * `detail` - The details of the process
+/// * `threads` - The threads of the process
+/// * `width` - The width of the terminal, long command lines are wrapped to it
+fn detail_lines(detail: &ProcessDetail, threads: Option<&ThreadData>, width: usize) -> Vec<(String, Color)> {
+  let unavailable = (String::from("  Not available, permission denied?"), Color::Red);
+  let mut lines: Vec<(String, Color)> = Vec::new();
+  lines.push((
+    format!("{} ({})  state {}  parent {}", detail.name, detail.pid, detail.state, detail.ppid),
+    Color::Reset,
+  ));
+  lines.push((format!("Executable: {}", detail.exe.as_deref().unwrap_or("?")), Color::Reset));
+  lines.push((format!("Working directory: {}", detail.cwd.as_deref().unwrap_or("?")), Color::Reset));
+
+  lines.push((String::from("Command line"), Color::DarkCyan));
+  let command = if detail.arguments.is_empty() { format!("[{}]", detail.name) } else { detail.arguments.join(" ") };
+  let chars: Vec<char> = command.chars().collect();
+  for chunk in chars.chunks(width.saturating_sub(2).max(1)) {
+    lines.push((format!("  {}", chunk.iter().collect::<String>()), Color::Reset));
+  }
+
+  if let Some(threads) = threads {
+    lines.push((format!("Threads ({})", threads.count), Color::DarkCyan));
+    lines.push((format!("  {}", thread_header()), Color::DarkGreen));
+    for thread in &threads.list {
+      lines.push((format!("  {}", thread_line(thread)), Color::Reset));
+    }
+  }
+
+  match &detail.fds {
+    Some(fds) => {
+      lines.push((format!("Open files ({})", fds.len()), Color::DarkCyan));
+      for (fd, target) in fds {
+        lines.push((format!("  {:>5} {}", fd, target), Color::Reset));
+      }
+    },
+    None => {
+      lines.push((String::from("Open files"), Color::DarkCyan));
+      lines.push(unavailable.clone());
+    },
+  }
+
+  match &detail.maps {
+    Some(maps) => {
+      lines.push((format!("Memory maps ({} mappings, {})", maps.count, utils::parse_size(&maps.total)), Color::DarkCyan));
+      lines.push((
+        format!(
+          "  heap {}  stack {}  code {}  anonymous {}",
+          utils::parse_size(&maps.heap),
+          utils::parse_size(&maps.stack),
+          utils::parse_size(&maps.executable),
+          utils::parse_size(&maps.anonymous),
+        ),
+        Color::Reset,
+      ));
+      for (path, size) in &maps.files {
+        lines.push((format!("  {:>9} {}", utils::parse_size(size), path), Color::Reset));
+      }
+    },
+    None => {
+      lines.push((String::from("Memory maps"), Color::DarkCyan));
+      lines.push(unavailable.clone());
+    },
+  }
+
+  lines.push((String::from("Limits"), Color::DarkCyan));
+  match &detail.limits {
+    Some(limits) => {
+      lines.push((format!("  {:<26}{:<21}{:<21}{}", "RESOURCE", "SOFT", "HARD", "UNITS"), Color::DarkGreen));
+      for limit in limits {
+        lines.push((format!("  {:<26}{:<21}{:<21}{}", limit.name, limit.soft, limit.hard, limit.units), Color::Reset));
+      }
+    },
+    None => lines.push(unavailable.clone()),
+  }
+
+  lines.push((String::from("Control groups"), Color::DarkCyan));
+  match &detail.cgroups {
+    Some(cgroups) => {
+      for cgroup in cgroups {
+        lines.push((format!("  {}", cgroup), Color::Reset));
+      }
+    },
+    None => lines.push(unavailable.clone()),
+  }
+
+  lines.push((String::from("Namespaces"), Color::DarkCyan));
+  match &detail.namespaces {
+    Some(namespaces) => {
+      for (name, id) in namespaces {
+        lines.push((format!("  {:<18} {}", name, id), Color::Reset));
+      }
+    },
+    None => lines.push(unavailable.clone()),
+  }
+
+  match &detail.environment {
+    Some(environment) => {
+      lines.push((format!("Environment ({})", environment.len()), Color::DarkCyan));
+      for variable in environment {
+        lines.push((format!("  {}", variable), Color::Reset));
+      }
+    },
+    None => {
+      lines.push((String::from("Environment"), Color::DarkCyan));
+      lines.push(unavailable);
+    },
+  }
+  lines
+}
+
+/// Prints the threads of the chosen process, the busiest first
+/// ### Parameters
+/// * `sys` - The system data with the threads of the process
+/// * `state` - The state of the UI, the selection is clamped to the threads
+/// * `term_size` - The size of the terminal
+fn print_thread_table(sys: &SystemData, state: &mut UIState, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let width = term_size.0 as usize;
+  state.selected_process = None;
+  let pid = match state.detail {
+    Some(pid) => pid,
+    None => {
+      execute!(stdout(), MoveTo(0, 2), Clear(FromCursorDown), SetForegroundColor(Color::DarkCyan))?;
+      print!("{}", truncate_end("Press H on a process or d to choose a PID", width));
+      execute!(stdout(), ResetColor)?;
+      return Ok(());
+    },
+  };
+  let process = sys.processes.list.iter().find(|process| process.pid == pid);
+  let threads = match &sys.threads {
+    Some(threads) if threads.pid == pid => threads,
+    _ => {
+      execute!(stdout(), MoveTo(0, 2), Clear(FromCursorDown))?;
+      match process {
+        Some(_) => print!("{}", truncate_end(&format!("Reading the threads of process {}…", pid), width)),
+        None => {
+          execute!(stdout(), SetForegroundColor(Color::Red))?;
+          print!("{}", truncate_end(&format!("Process {} is not running", pid), width));
+        },
+      }
+      execute!(stdout(), ResetColor)?;
+      return Ok(());
+    },
+  };
+  let name = process.map(|process| process.name.clone()).unwrap_or_default();
+  state.selected_process = Some((pid, name.clone()));
+  let mut list: Vec<&ThreadInfo> = threads.list.iter().collect();
+  list.sort_by(|a, b| b.cpu.partial_cmp(&a.cpu).unwrap_or(Ordering::Equal).then_with(|| a.tid.cmp(&b.tid)));
+
+  let total: f32 = list.iter().map(|thread| thread.cpu).sum();
+  execute!(stdout(), MoveTo(0, 2), Clear(CurrentLine))?;
+  print!("{}", truncate_end(&format!("{} ({}): {} threads, {:.1}% CPU", name, pid, threads.count, total), width));
+  execute!(stdout(), MoveTo(0, 3), Clear(CurrentLine), SetBackgroundColor(Color::DarkGreen), SetForegroundColor(Color::Black))?;
+  print!("{:<width$}", truncate_end(&thread_header(), width), width = width);
+  execute!(stdout(), ResetColor)?;
+
+  // Rows between the column header and the bottom bar
+  let height = (term_size.1 as usize).saturating_sub(5).max(1);
+  state.selection.1 = state.selection.1.min(list.len());
+  let scroll = state.selection.1.saturating_sub(height);
+  let mut row: u16 = 4;
+  for (index, thread) in list.iter().enumerate().skip(scroll).take(height) {
+    execute!(stdout(), MoveTo(0, row), Clear(CurrentLine))?;
+    let line = truncate_end(&thread_line(thread), width);
+    if state.selection.1 == index + 1 {
+      execute!(stdout(), SetBackgroundColor(Color::DarkCyan), SetForegroundColor(Color::Black))?;
+      print!("{:<width$}", line, width = width);
+      execute!(stdout(), ResetColor)?;
+    } else {
+      print!("{}", line);
+    }
+    row += 1;
+  }
+  execute!(stdout(), MoveTo(0, row), Clear(FromCursorDown))?;
+  Ok(())
+}
+
+/// Returns the column header of the thread listings
+fn thread_header() -> String {
+  format!("{:>7} {} {:>6} {:>4} {}", "TID", "S", "CPU%", "CORE", "NAME")
+}
+
+/// Formats the thread as a row of the thread listings
+/// ### Parameters
+/// * `thread` - The thread to format
+fn thread_line(thread: &ThreadInfo) -> String {
+  format!("{:>7} {} {:>6.1} {:>4} {}", thread.tid, thread.state, thread.cpu, thread.processor, thread.name)
+}
+
+/// Finds the index of the row the selection jumps to, wrapping around the ends of the table
+/// ### Parameters
+/// * `rows` - The rows of the process table
+/// * `search` - The search the rows are matched against
+/// * `selection` - The selected row, 0 being the menu header and 1 the first process
+/// * `jump` - Where the search starts from and in which direction
+fn find_match(rows: &[proctree::ProcessRow], search: &ProcessFilter, selection: usize, jump: Jump) -> Option<usize> {
+  let count = rows.len();
+  if count == 0 {
+    return None;
+  }
+  let current = selection.saturating_sub(1).min(count - 1);
+  let order: Vec<usize> = match jump {
+    Jump::First => (0..count).map(|offset| (current + offset) % count).collect(),
+    Jump::Next => (1..=count).map(|offset| (current + offset) % count).collect(),
+    Jump::Previous => (1..=count).map(|offset| (current + count - offset) % count).collect(),
+  };
+  order.into_iter().find(|&index| search.matches(rows[index].process))
 }
 
-pub fn print_system_data(thr_data: &Arc<Mutex<SystemData>>, selection: &mut (usize, usize), term_size: (u16, u16)) {
-  let mut shared_data = thr_data.lock().unwrap();
-}
\ No newline at end of file
+/// Prints the open text prompt over the bottom bar
+/// ### Parameters
+/// * `prompt` - The prompt to print
+/// * `term_size` - The size of the terminal
+pub fn print_prompt(prompt: &Prompt, term_size: (u16, u16)) -> Result<(), Box<dyn Error>> {
+  let label = match prompt.kind {
+    PromptKind::Search => "Search: ",
+    PromptKind::Filter => "Filter: ",
+    PromptKind::Pid => "PID: ",
+  };
+  execute!(stdout(), MoveTo(0, term_size.1.saturating_sub(1)), Clear(CurrentLine), SetForegroundColor(Color::DarkCyan))?;
+  print!(" {}", label);
+  execute!(stdout(), ResetColor)?;
+  let mut line = format!("{}█", prompt.input);
+  if let Some(error) = &prompt.error {
+    line += &format!("  {}", error);
+  }
+  let line: String = line.chars().take((term_size.0 as usize).saturating_sub(label.len() + 1)).collect();
+  let cursor = prompt.input.chars().count() + 1;
+  print!("{}", line.chars().take(cursor).collect::<String>());
+  execute!(stdout(), SetForegroundColor(Color::Red))?;
+  print!("{}", line.chars().skip(cursor).collect::<String>());
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Prints a bar that is as long as the percentage of the given terminal width
+/// ### Parameters
+/// * `max_width` - The max width of the bar
+/// * `percentage` - The percentage of the max width the bar is going to be
+pub fn print_bar(max_width: u16, percentage: f32, color: Color) -> Result<(), Box<dyn Error>> {
+  execute!(stdout(), SetForegroundColor(color))?;
+  let block_count = max_width as f32 / 100_f32 * percentage;
+  let mut index: u16 = 0;
+  let floored = block_count as u16;
+  // Print the full bars
+  while index < floored {
+    print!("⧛");
+    index += 1;
+  }
+  // Determine the last bar from decimal
+  if floored != 100 {
+    if (block_count - floored as f32) <= 0.5 {
+      print!("⧙");
+    } else {
+      print!(" ");
+    }
+  }
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Prints a bar made of differently coloured segments that are as long as their
+/// percentages of the given width
+/// ### Parameters
+/// * `max_width` - The max width of the bar
+/// * `segments` - The percentages of the max width and the colours of the segments
+pub fn print_stacked_bar(max_width: u16, segments: &[(f32, Color)]) -> Result<(), Box<dyn Error>> {
+  let mut total: f32 = 0_f32;
+  let mut printed: u16 = 0;
+  for (percentage, color) in segments {
+    // Rounding the end of each segment keeps the rounding errors from adding up
+    total = (total + percentage).min(100_f32);
+    let end = (max_width as f32 / 100_f32 * total).round() as u16;
+    execute!(stdout(), SetForegroundColor(*color))?;
+    while printed < end {
+      print!("⧛");
+      printed += 1;
+    }
+  }
+  execute!(stdout(), ResetColor)?;
+  Ok(())
+}
+
+/// Shortens the text to the given width by cutting from the start, as the end of
+/// device paths and mountpoints is usually the interesting part
+/// ### Parameters
+/// * `text` - The text to shorten
+/// * `width` - The max width of the text
+pub fn truncate_start(text: &str, width: usize) -> String {
+  let length = text.chars().count();
+  if length <= width {
+    return text.to_string();
+  }
+  let mut shortened: String = String::from("…");
+  shortened += &text.chars().skip(length - width + 1).collect::<String>();
+  shortened
+}
+
+/// Shortens the text to the given width by cutting from the end
+/// ### Parameters
+/// * `text` - The text to shorten
+/// * `width` - The max width of the text
+pub fn truncate_end(text: &str, width: usize) -> String {
+  if text.chars().count() <= width {
+    return text.to_string();
+  }
+  let mut shortened: String = text.chars().take(width.saturating_sub(1)).collect();
+  shortened += "+";
+  shortened
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pids(processes: &[&ProcessInfo]) -> Vec<u32> {
+    processes.iter().map(|process| process.pid).collect()
+  }
+
+  #[test]
+  fn sorts_by_the_column_and_breaks_ties_by_pid() {
+    let list = [
+      ProcessInfo::fixture(3, 1, "root", 5.0, 10),
+      ProcessInfo::fixture(1, 1, "www", 20.0, 30),
+      ProcessInfo::fixture(2, 1, "root", 5.0, 20),
+    ];
+    let mut processes: Vec<&ProcessInfo> = list.iter().collect();
+    sort_processes(&mut processes, SortColumn::Cpu, true);
+    // The ties stay in pid order whatever the direction
+    assert_eq!(pids(&processes), vec![1, 2, 3]);
+    sort_processes(&mut processes, SortColumn::Cpu, false);
+    assert_eq!(pids(&processes), vec![2, 3, 1]);
+    sort_processes(&mut processes, SortColumn::Memory, true);
+    assert_eq!(pids(&processes), vec![1, 2, 3]);
+    sort_processes(&mut processes, SortColumn::User, false);
+    assert_eq!(pids(&processes), vec![2, 3, 1]);
+  }
+
+  #[test]
+  fn finds_matches_around_the_selection() {
+    let list = [
+      ProcessInfo::fixture(1, 1, "root", 0.0, 0),
+      ProcessInfo::fixture(2, 1, "www", 0.0, 0),
+      ProcessInfo::fixture(3, 1, "root", 0.0, 0),
+      ProcessInfo::fixture(4, 1, "www", 0.0, 0),
+    ];
+    let processes: Vec<&ProcessInfo> = list.iter().collect();
+    let rows = proctree::build_list(&processes);
+    let search = ProcessFilter::parse("user:www").unwrap();
+    // The selection is 1 based, the matches are the indexes of the rows
+    assert_eq!(find_match(&rows, &search, 2, Jump::First), Some(1));
+    assert_eq!(find_match(&rows, &search, 2, Jump::Next), Some(3));
+    assert_eq!(find_match(&rows, &search, 4, Jump::Next), Some(1));
+    assert_eq!(find_match(&rows, &search, 2, Jump::Previous), Some(3));
+    // The menu header row searches from the first process
+    assert_eq!(find_match(&rows, &search, 0, Jump::First), Some(1));
+    let nothing = ProcessFilter::parse("user:nobody").unwrap();
+    assert_eq!(find_match(&rows, &nothing, 1, Jump::Next), None);
+    assert_eq!(find_match(&[], &search, 1, Jump::First), None);
+  }
+}
@@ -54,4 +54,66 @@ pub fn parse_size(refsize: &u64) -> String {
       unit_index += 1;
   }
   return format!("{:.2}{}", size, unit_vec[unit_index]);
-}
\ No newline at end of file
+}
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Formats the start time of a process like `ps` does, as the clock time for
+/// processes started within the last day and as the date for older ones
+/// ### Parameters
+/// * `timestamp` - The reference to the start time in seconds since the unix epoch
+/// * `now` - The reference to the current time in seconds since the unix epoch
+pub fn parse_start_time(timestamp: &u64, now: &u64) -> String {
+  let (_year, month, day, hour, minute, _second) = local_time(*timestamp);
+  if now.saturating_sub(*timestamp) < 86400 {
+    return format!("{:02}:{:02}", hour, minute);
+  }
+  format!("{}{:02}", MONTHS[(month - 1) as usize], day)
+}
+
+/// Splits the unix timestamp into the local year, month, day, hour, minute and second
+/// ### Parameters
+/// * `timestamp` - The time in seconds since the unix epoch
+#[cfg(unix)]
+pub fn local_time(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+  let time = timestamp as libc::time_t;
+  let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+  if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+    return utc_time(timestamp);
+  }
+  (
+    tm.tm_year as i64 + 1900,
+    tm.tm_mon as u32 + 1,
+    tm.tm_mday as u32,
+    tm.tm_hour as u32,
+    tm.tm_min as u32,
+    tm.tm_sec as u32,
+  )
+}
+
+/// Splits the unix timestamp into the year, month, day, hour, minute and second,
+/// other platforms than unix show the time in UTC
+/// ### Parameters
+/// * `timestamp` - The time in seconds since the unix epoch
+#[cfg(not(unix))]
+pub fn local_time(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+  utc_time(timestamp)
+}
+
+/// Splits the unix timestamp into the UTC year, month, day, hour, minute and second
+/// ### Parameters
+/// * `timestamp` - The time in seconds since the unix epoch
+pub fn utc_time(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+  // Days to civil date conversion from Howard Hinnant's date algorithms
+  let days = (timestamp / 86400) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+  let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  let seconds = timestamp % 86400;
+  (year, month, day, (seconds / 3600) as u32, (seconds / 60 % 60) as u32, (seconds % 60) as u32)
+}